@@ -151,7 +151,7 @@ function EditorContent({ recordingId }: { recordingId: string | null }) {
             try {
               console.log('Getting unified transcript insights...')
               const startTime = Date.now()
-              const insights = await getTranscriptInsights(text)
+              const insights = await getTranscriptInsights(text, selectedRecording.path)
               console.log(`Unified insights received in ${Date.now() - startTime}ms`, {
                 hasSummary: !!insights.summary,
                 hasActions: !!insights.actions,
@@ -198,7 +198,7 @@ function EditorContent({ recordingId }: { recordingId: string | null }) {
             try {
               console.log('Falling back to separate summary generation...')
               const startTime = Date.now()
-              const result = await summarizeTranscript(text)
+              const result = await summarizeTranscript(text, selectedRecording.path)
               console.log(`Summary generated in ${Date.now() - startTime}ms`)
               setSummary(result)
             } catch (error) {
@@ -212,7 +212,7 @@ function EditorContent({ recordingId }: { recordingId: string | null }) {
             try {
               console.log('Falling back to separate topics generation...')
               const startTime = Date.now()
-              const aiTopics = await extractKeyTopicsAI(text)
+              const aiTopics = await extractKeyTopicsAI(text, selectedRecording.path)
               console.log(`Topics generated in ${Date.now() - startTime}ms`)
               setTopics(normalizeTopics(aiTopics))
             } catch (error) {
@@ -225,7 +225,7 @@ function EditorContent({ recordingId }: { recordingId: string | null }) {
             try {
               console.log('Falling back to separate actions generation...')
               const startTime = Date.now()
-              const aiActions = await recommendActions(text)
+              const aiActions = await recommendActions(text, selectedRecording.path)
               console.log(`Actions generated in ${Date.now() - startTime}ms`)
                const derivedActions = aiActions.length > 0
                  ? aiActions
@@ -639,7 +639,7 @@ function EditorContent({ recordingId }: { recordingId: string | null }) {
               setSummary(null)
               
               try {
-                const insights = await getTranscriptInsights(updatedTranscript.text)
+                const insights = await getTranscriptInsights(updatedTranscript.text, selectedRecording.path)
                 
                 if (insights.topics) {
                   setTopics(normalizeTopics(insights.topics))
@@ -669,14 +669,14 @@ function EditorContent({ recordingId }: { recordingId: string | null }) {
               
               // Fallback: separate generation for missing fields
               try {
-                const aiTopics = await extractKeyTopicsAI(updatedTranscript.text)
+                const aiTopics = await extractKeyTopicsAI(updatedTranscript.text, selectedRecording.path)
                 setTopics(normalizeTopics(aiTopics))
               } catch {
                 const extracted = extractKeyTopics(updatedTranscript.text)
                 setTopics(normalizeTopics(extracted))
               }
               try {
-                const aiActions = await recommendActions(updatedTranscript.text)
+                const aiActions = await recommendActions(updatedTranscript.text, selectedRecording.path)
                 const normalized: Action[] = aiActions.map((action) => ({
                   id: actionIdFromContent(action.title, action.description),
                   title: action.title,
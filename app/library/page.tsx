@@ -5,15 +5,20 @@ import { Footer } from '@/components/Footer';
 import { RecordingsTable } from '@/components/RecordingsTable';
 import { pickFolder, scanFolderForAudio } from '@/lib/fs/commands';
 import { getLastFolder, setLastFolder } from '@/lib/fs/config';
+import { AudioStorageMode, getSettings, updateSettings } from '@/lib/settings/commands';
 import { AudioItem } from '@/lib/types';
 
 export default function LibraryPage() {
   const [recordings, setRecordings] = useState<AudioItem[]>([]);
   const [loading, setLoading] = useState(false);
   const [totalDuration, setTotalDuration] = useState(0);
+  const [audioStorage, setAudioStorage] = useState<AudioStorageMode | null>(null);
 
   useEffect(() => {
     loadLastFolder();
+    getSettings()
+      .then((settings) => setAudioStorage(settings.audioStorage))
+      .catch((err) => console.error('Failed to load settings:', err));
   }, []);
 
   useEffect(() => {
@@ -54,6 +59,17 @@ export default function LibraryPage() {
     }
   }
 
+  async function handleAudioStorageChange(mode: AudioStorageMode) {
+    try {
+      // Only applies to recordings transcribed from now on
+      const settings = await getSettings();
+      const saved = await updateSettings({ ...settings, audioStorage: mode });
+      setAudioStorage(saved.audioStorage);
+    } catch (err) {
+      console.error('Failed to save audio storage setting:', err);
+    }
+  }
+
   function formatDuration(seconds: number): string {
     const hours = Math.floor(seconds / 3600);
     const minutes = Math.floor((seconds % 3600) / 60);
@@ -75,6 +91,20 @@ export default function LibraryPage() {
               </h1>
             </div>
             <div className="flex items-center gap-2">
+              {audioStorage && (
+                <label className="flex items-center gap-2 text-xs text-slate-500">
+                  Audio storage
+                  <select
+                    value={audioStorage}
+                    onChange={(e) => handleAudioStorageChange(e.target.value as AudioStorageMode)}
+                    title="Copy keeps recordings playable when the original file is gone; reference links to the original instead"
+                    className="bg-slate-deep border border-slate-border rounded-lg px-2 py-2 text-sm text-slate-300"
+                  >
+                    <option value="copy">Copy</option>
+                    <option value="reference">Reference</option>
+                  </select>
+                </label>
+              )}
               <button
                 onClick={handleChooseFolder}
                 disabled={loading}
@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::default::get_codecs;
+
+use super::open_format;
+
+/// A file extension the library indexes and transcription accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaFormat {
+    pub extension: &'static str,
+    /// Video container; only its first audio track is used.
+    pub video: bool,
+    /// Symphonia demuxes the container. Codecs it lacks (Opus) still go
+    /// through `transcode_to_wav`.
+    pub demuxed: bool,
+    /// The OpenAI transcription endpoint takes the file as is.
+    pub uploadable: bool,
+}
+
+const fn format(extension: &'static str, video: bool, demuxed: bool, uploadable: bool) -> MediaFormat {
+    MediaFormat {
+        extension,
+        video,
+        demuxed,
+        uploadable,
+    }
+}
+
+/// Every supported extension, lowercase.
+pub const MEDIA_FORMATS: &[MediaFormat] = &[
+    format("mp3", false, true, true),
+    format("m4a", false, true, true),
+    format("wav", false, true, true),
+    format("flac", false, true, true),
+    format("ogg", false, true, true),
+    format("aac", false, true, false),
+    format("opus", false, true, false),
+    format("webm", false, true, true),
+    format("mka", false, true, false),
+    format("amr", false, false, false),
+    format("wma", false, false, false),
+    format("mp4", true, true, true),
+    format("mov", true, true, false),
+    format("mkv", true, true, false),
+];
+
+/// Registry entry for the extension of `path`.
+pub fn lookup(path: &Path) -> Option<&'static MediaFormat> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    MEDIA_FORMATS.iter().find(|format| format.extension == ext)
+}
+
+pub fn is_supported(path: &Path) -> bool {
+    lookup(path).is_some()
+}
+
+/// Comma separated list of supported extensions, for error messages.
+pub fn supported_list() -> String {
+    MEDIA_FORMATS.iter().map(|f| f.extension).collect::<Vec<_>>().join(", ")
+}
+
+/// Whether symphonia can decode the first audio track of `path` itself.
+/// Only reads the container header.
+pub fn decodes_natively(path: &Path) -> bool {
+    if !lookup(path).is_some_and(|format| format.demuxed) {
+        return false;
+    }
+    let Ok(format) = open_format(path) else {
+        return false;
+    };
+    format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .is_some_and(|track| get_codecs().make(&track.codec_params, &DecoderOptions::default()).is_ok())
+}
+
+/// A file symphonia can decode: the original, or a temporary WAV transcode
+/// that is removed on drop.
+pub enum Decodable {
+    Original(PathBuf),
+    Transcoded(PathBuf),
+}
+
+impl Decodable {
+    pub fn path(&self) -> &Path {
+        match self {
+            Decodable::Original(path) | Decodable::Transcoded(path) => path,
+        }
+    }
+}
+
+impl Drop for Decodable {
+    fn drop(&mut self) {
+        if let Decodable::Transcoded(path) = self {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// `path` itself when symphonia decodes it, otherwise a WAV transcode made
+/// with ffmpeg.
+pub fn decodable(path: &Path) -> Result<Decodable, String> {
+    if decodes_natively(path) {
+        return Ok(Decodable::Original(path.to_path_buf()));
+    }
+    log::info!("Transcoding {} with ffmpeg", path.display());
+    transcode_to_wav(path).map(Decodable::Transcoded)
+}
+
+fn ffmpeg_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        Command::new("ffmpeg")
+            .arg("-version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    })
+}
+
+/// Extracts the first audio stream of `path` into a 16-bit WAV in the temp
+/// directory, keeping its sample rate and channels. Needs `ffmpeg` on the
+/// PATH.
+pub fn transcode_to_wav(path: &Path) -> Result<PathBuf, String> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if !ffmpeg_available() {
+        return Err(format!("Decoding .{} files needs ffmpeg installed and on the PATH", ext));
+    }
+
+    let output = std::env::temp_dir().join(format!(
+        "transcode-{}-{}.wav",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    run_ffmpeg(path, &["-c:a", "pcm_s16le", "-f", "wav"], &output)?;
+    Ok(output)
+}
+
+/// Copies the first audio stream of the video `path` into `output` without
+/// re-encoding; the container follows the extension of `output`. Needs
+/// `ffmpeg` on the PATH.
+pub fn extract_audio_track(path: &Path, output: &Path) -> Result<(), String> {
+    if !ffmpeg_available() {
+        return Err("Extracting the audio track needs ffmpeg installed and on the PATH".to_string());
+    }
+    run_ffmpeg(path, &["-c:a", "copy"], output)
+}
+
+// Runs ffmpeg on the first audio stream of `input`, removing a partial
+// `output` on failure.
+fn run_ffmpeg(input: &Path, codec_args: &[&str], output: &Path) -> Result<(), String> {
+    let result = Command::new("ffmpeg")
+        .args(["-nostdin", "-v", "error", "-y", "-i"])
+        .arg(input)
+        .args(["-map", "0:a:0", "-vn"])
+        .args(codec_args)
+        .arg(output)
+        .stdout(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+
+    if !result.status.success() {
+        let _ = fs::remove_file(output);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(format!("ffmpeg could not decode {}: {}", input.display(), stderr.trim()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_extensions_case_insensitively() {
+        assert_eq!(lookup(Path::new("/a/talk.MKV")).map(|f| f.video), Some(true));
+        assert!(is_supported(Path::new("memo.opus")));
+        assert!(is_supported(Path::new("call.amr")));
+        assert!(!is_supported(Path::new("notes.txt")));
+        assert!(!is_supported(Path::new("wav")));
+        assert!(!lookup(Path::new("x.wma")).unwrap().demuxed);
+    }
+
+    #[test]
+    fn extensions_are_unique_and_lowercase() {
+        for (i, format) in MEDIA_FORMATS.iter().enumerate() {
+            assert_eq!(format.extension, format.extension.to_lowercase());
+            assert!(MEDIA_FORMATS[i + 1..].iter().all(|f| f.extension != format.extension));
+        }
+        assert!(supported_list().starts_with("mp3, m4a, wav"));
+    }
+
+    #[test]
+    fn unknown_extensions_are_not_decoded_natively() {
+        assert!(!decodes_natively(Path::new("/nonexistent/file.wma")));
+        assert!(!decodes_natively(Path::new("/nonexistent/file.mp3")));
+    }
+}
@@ -0,0 +1,472 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{CodecType, DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::{Hint, ProbeResult};
+use symphonia::core::units::TimeBase;
+use symphonia::default::{get_codecs, get_probe};
+
+pub mod formats;
+pub mod peaks;
+pub mod recorded_at;
+
+/// Bump when `probe` reads more; library entries and manifests probed by an
+/// older version are probed again.
+pub const PROBE_VERSION: i32 = 2;
+
+/// Sample rate expected by Whisper models.
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// Mono PCM samples in the range [-1.0, 1.0].
+#[cfg(feature = "local-whisper")]
+pub struct DecodedAudio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+#[cfg(feature = "local-whisper")]
+impl DecodedAudio {
+    pub fn duration_seconds(&self) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.samples.len() as f64 / self.sample_rate as f64
+    }
+}
+
+pub fn open_format(path: &Path) -> Result<Box<dyn FormatReader>, String> {
+    Ok(probe_file(path)?.format)
+}
+
+/// `open_format` reading from `source` instead of opening `path`, which only
+/// gives the extension hint.
+pub fn open_format_from(path: &Path, source: Box<dyn MediaSource>) -> Result<Box<dyn FormatReader>, String> {
+    Ok(probe_source(path, source)?.format)
+}
+
+fn probe_file(path: &Path) -> Result<ProbeResult, String> {
+    let source = fs::File::open(path).map_err(|e| e.to_string())?;
+    probe_source(path, Box::new(source))
+}
+
+fn probe_source(path: &Path, source: Box<dyn MediaSource>) -> Result<ProbeResult, String> {
+    let mss = MediaSourceStream::new(source, Default::default());
+    let mut hint = Hint::new();
+
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("Failed to probe {}: {}", path.display(), e))
+}
+
+/// Everything read from a recording without decoding it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioProbe {
+    pub duration: Option<AudioDuration>,
+    pub format: AudioFormat,
+    pub tags: AudioTags,
+    pub recorded_at: Option<recorded_at::RecordedAt>,
+}
+
+/// Stream parameters of the first audio track.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AudioFormat {
+    /// Short codec name, e.g. `mp3`, `aac`, `pcm_s16le`.
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// Average bits per second over the file, cover art excluded.
+    pub bitrate: Option<u32>,
+}
+
+/// Embedded ID3, MP4 or RIFF INFO metadata.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// As written in the file, formats vary.
+    pub date: Option<String>,
+    pub comment: Option<String>,
+    pub has_cover_art: bool,
+}
+
+impl AudioTags {
+    /// Fills fields still missing from `revision`; returns the size of its cover art.
+    fn merge(&mut self, revision: &MetadataRevision) -> u64 {
+        for tag in revision.tags() {
+            let field = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => &mut self.title,
+                Some(StandardTagKey::Artist) | Some(StandardTagKey::AlbumArtist) => &mut self.artist,
+                Some(StandardTagKey::Date) | Some(StandardTagKey::ReleaseDate) | Some(StandardTagKey::OriginalDate) => {
+                    &mut self.date
+                }
+                Some(StandardTagKey::Comment) | Some(StandardTagKey::Description) => &mut self.comment,
+                _ => continue,
+            };
+            let value = tag.value.to_string();
+            if field.is_none() && !value.trim().is_empty() {
+                *field = Some(value.trim().to_string());
+            }
+        }
+        self.has_cover_art |= !revision.visuals().is_empty();
+        revision.visuals().iter().map(|v| v.data.len() as u64).sum()
+    }
+}
+
+/// Probes `path` for its duration, stream parameters and tags. `None` if
+/// symphonia can't read the container.
+pub fn probe(path: &Path) -> Option<AudioProbe> {
+    let mut probed = probe_file(path).ok()?;
+
+    // Container tags (MP4, RIFF) take precedence over ones found while
+    // probing (ID3v2 in front of the stream)
+    let mut tags = AudioTags::default();
+    let mut cover_bytes = 0;
+    let mut date_tags = Vec::new();
+    if let Some(revision) = probed.format.metadata().current() {
+        cover_bytes += tags.merge(revision);
+        date_tags.extend(date_tag_values(revision));
+    }
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        cover_bytes += tags.merge(revision);
+        date_tags.extend(date_tag_values(revision));
+    }
+
+    let mut format = probed.format;
+    let mut audio_format = match format.tracks().iter().find(|t| t.codec_params.codec != CODEC_TYPE_NULL) {
+        Some(track) => AudioFormat {
+            codec: codec_name(track.codec_params.codec),
+            sample_rate: track.codec_params.sample_rate,
+            channels: track.codec_params.channels.map(|c| c.count() as u32),
+            bitrate: None,
+        },
+        None => AudioFormat::default(),
+    };
+
+    let duration = read_duration(format.as_mut(), path);
+    if let Some(d) = duration.filter(|d| d.seconds > 0.0) {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let bits = size.saturating_sub(cover_bytes) as f64 * 8.0;
+        audio_format.bitrate = Some((bits / d.seconds).round() as u32).filter(|b| *b > 0);
+    }
+
+    Some(AudioProbe {
+        duration,
+        format: audio_format,
+        tags,
+        recorded_at: recorded_at::detect(path, &date_tags),
+    })
+}
+
+fn codec_name(codec: CodecType) -> Option<String> {
+    match get_codecs().get_codec(codec) {
+        Some(descriptor) => Some(descriptor.short_name.to_string()),
+        // Demuxed but decoded through ffmpeg
+        None if codec == CODEC_TYPE_OPUS => Some("opus".to_string()),
+        None => None,
+    }
+}
+
+fn date_tag_values(revision: &MetadataRevision) -> Vec<(String, String)> {
+    revision
+        .tags()
+        .iter()
+        .filter(|tag| tag.std_key == Some(StandardTagKey::Date))
+        .map(|tag| (tag.key.clone(), tag.value.to_string()))
+        .collect()
+}
+
+/// Length of a recording and how it was determined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioDuration {
+    pub seconds: f64,
+    /// False when extrapolated from the part of the file that could be read.
+    pub exact: bool,
+}
+
+/// Duration of the first audio track. Taken from the container header when
+/// it declares the frame count; otherwise the packets are scanned (VBR MP3
+/// without a Xing header, fragmented M4A). If the scan stops early on
+/// damaged data, the rest is extrapolated from the file size.
+fn read_duration(format: &mut dyn FormatReader, path: &Path) -> Option<AudioDuration> {
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)?;
+    let track_id = track.id;
+    let params = &track.codec_params;
+    let time_base = params
+        .time_base
+        .or_else(|| params.sample_rate.map(|rate| TimeBase::new(1, rate)))?;
+
+    if let Some(n_frames) = params.n_frames {
+        return Some(AudioDuration {
+            seconds: seconds_at(time_base, n_frames),
+            exact: true,
+        });
+    }
+
+    let mut end_ts = 0u64;
+    let mut bytes_read = 0u64;
+    let mut complete = true;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => {
+                log::warn!("Duration scan of {} stopped early: {}", path.display(), e);
+                complete = false;
+                break;
+            }
+        };
+        if packet.track_id() == track_id {
+            end_ts = end_ts.max(packet.ts() + packet.dur());
+            bytes_read += packet.data.len() as u64;
+        }
+    }
+
+    if end_ts == 0 {
+        return None;
+    }
+    let seconds = seconds_at(time_base, end_ts);
+    if complete {
+        return Some(AudioDuration { seconds, exact: true });
+    }
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Some(AudioDuration {
+        seconds: seconds * (size as f64 / bytes_read as f64).max(1.0),
+        exact: false,
+    })
+}
+
+fn seconds_at(time_base: TimeBase, ts: u64) -> f64 {
+    let time = time_base.calc_time(ts);
+    time.seconds as f64 + time.frac
+}
+
+/// Decodes the first audio track of `path`, downmixes it to mono and
+/// resamples it to `target_rate`. Codecs symphonia lacks are transcoded
+/// with ffmpeg first.
+#[cfg(feature = "local-whisper")]
+pub fn decode_to_mono(path: &Path, target_rate: u32) -> Result<DecodedAudio, String> {
+    let mut samples = Vec::new();
+    decode_mono_stream(path, target_rate, |decoded| {
+        samples.extend_from_slice(decoded);
+        Ok(())
+    })?;
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: target_rate,
+    })
+}
+
+/// `decode_to_mono` that hands the samples to `on_samples` packet by packet
+/// instead of collecting them. An error from `on_samples` stops decoding.
+/// Returns the number of samples produced.
+pub fn decode_mono_stream(
+    path: &Path,
+    target_rate: u32,
+    mut on_samples: impl FnMut(&[f32]) -> Result<(), String>,
+) -> Result<usize, String> {
+    let source = formats::decodable(path)?;
+    let mut format = open_format(source.path())?;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("No audio track found in {}", path.display()))?;
+    let track_id = track.id;
+
+    let mut decoder = get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported codec: {}", e))?;
+
+    let mut mono: Vec<f32> = Vec::new();
+    let mut resampled: Vec<f32> = Vec::new();
+    let mut produced = 0;
+    let mut resampler: Option<Resampler> = None;
+    let mut sample_buf: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(format!("Failed to read packet: {}", e)),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Corrupt frames are skipped, the rest of the stream is still usable
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Failed to decode packet: {}", e)),
+        };
+
+        let spec = *decoded.spec();
+        if spec.rate == 0 {
+            return Err(format!("Unknown sample rate for {}", path.display()));
+        }
+        let channels = spec.channels.count().max(1);
+
+        let buf = match sample_buf.as_mut() {
+            Some(buf) if buf.capacity() >= decoded.capacity() * channels => buf,
+            _ => sample_buf.insert(SampleBuffer::<f32>::new(decoded.capacity() as u64, spec)),
+        };
+        buf.copy_interleaved_ref(decoded);
+
+        mono.clear();
+        mono.extend(buf.samples().chunks(channels).map(|frame| frame.iter().sum::<f32>() / channels as f32));
+        resampled.clear();
+        resampler
+            .get_or_insert_with(|| Resampler::new(spec.rate, target_rate))
+            .push(&mono, &mut resampled);
+        produced += resampled.len();
+        on_samples(&resampled)?;
+    }
+
+    if let Some(resampler) = resampler {
+        resampled.clear();
+        resampler.finish(&mut resampled);
+        produced += resampled.len();
+        on_samples(&resampled)?;
+    }
+    Ok(produced)
+}
+
+/// BLAKE3 of the audio track's packet data, hex encoded. Tags and cover art
+/// are not part of it, so retagging a file keeps its identity. Files symphonia
+/// can't demux are hashed whole.
+pub fn content_hash(path: &Path) -> Result<String, String> {
+    let mut hasher = blake3::Hasher::new();
+    if let Err(e) = hash_packets(path, &mut hasher) {
+        log::warn!("Hashing raw bytes of {}: {}", path.display(), e);
+        hasher.reset();
+        let file = fs::File::open(path).map_err(|e| e.to_string())?;
+        hasher.update_reader(file).map_err(|e| e.to_string())?;
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn hash_packets(path: &Path, hasher: &mut blake3::Hasher) -> Result<(), String> {
+    let mut format = open_format(path)?;
+    let track_id = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("No audio track found in {}", path.display()))?
+        .id;
+
+    let mut packets = 0;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(format!("Failed to read packet: {}", e)),
+        };
+        if packet.track_id() == track_id {
+            hasher.update(&packet.data);
+            packets += 1;
+        }
+    }
+
+    if packets == 0 {
+        return Err("No audio packets".to_string());
+    }
+    Ok(())
+}
+
+/// Linear interpolation resampler for input that arrives in pieces. Good
+/// enough for speech recognition input, not meant for playback.
+struct Resampler {
+    // Input samples per output sample
+    ratio: f64,
+    // Input not yet interpolated past; `pos` is relative to its first sample
+    pending: Vec<f32>,
+    pos: f64,
+}
+
+impl Resampler {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            ratio: from_rate as f64 / to_rate as f64,
+            pending: Vec::new(),
+            pos: 0.0,
+        }
+    }
+
+    fn push(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        if self.ratio == 1.0 {
+            out.extend_from_slice(samples);
+            return;
+        }
+        self.pending.extend_from_slice(samples);
+        // Each output sample needs the input sample after its position
+        while (self.pos.floor() as usize) + 1 < self.pending.len() {
+            out.push(self.interpolate());
+            self.pos += self.ratio;
+        }
+        let consumed = (self.pos.floor() as usize).min(self.pending.len());
+        self.pending.drain(..consumed);
+        self.pos -= consumed as f64;
+    }
+
+    /// Emits what is left, holding the last sample at the end of the input.
+    fn finish(mut self, out: &mut Vec<f32>) {
+        while (self.pos.floor() as usize) < self.pending.len() {
+            out.push(self.interpolate());
+            self.pos += self.ratio;
+        }
+    }
+
+    fn interpolate(&self) -> f32 {
+        let idx = self.pos.floor() as usize;
+        let frac = (self.pos - idx as f64) as f32;
+        let a = self.pending[idx];
+        let b = self.pending.get(idx + 1).copied().unwrap_or(a);
+        a + (b - a) * frac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resample(pieces: std::slice::Chunks<f32>, from_rate: u32, to_rate: u32) -> Vec<f32> {
+        let mut out = Vec::new();
+        let mut resampler = Resampler::new(from_rate, to_rate);
+        for piece in pieces {
+            resampler.push(piece, &mut out);
+        }
+        resampler.finish(&mut out);
+        out
+    }
+
+    #[test]
+    fn resampling_in_pieces_matches_resampling_at_once() {
+        let samples: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.05).sin()).collect();
+
+        let whole = resample(samples.chunks(samples.len()), 44_100, WHISPER_SAMPLE_RATE);
+        let pieces = resample(samples.chunks(37), 44_100, WHISPER_SAMPLE_RATE);
+
+        assert_eq!(whole.len(), 363);
+        assert_eq!(pieces.len(), whole.len());
+        assert!(whole.iter().zip(&pieces).all(|(a, b)| (a - b).abs() < 1e-5));
+        assert_eq!(resample(samples.chunks(100), 16_000, 16_000), samples);
+    }
+}
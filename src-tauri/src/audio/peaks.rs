@@ -0,0 +1,414 @@
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::io::MediaSource;
+use symphonia::default::get_codecs;
+
+use super::{formats, open_format_from};
+
+/// Peaks cache in a managed directory.
+pub const PEAKS_FILE: &str = "peaks.bin";
+
+/// Bump when the layout of `peaks.bin` or the level set changes.
+pub const PEAKS_FORMAT_VERSION: u32 = 1;
+
+/// Source frames per peak of each stored level, finest first. Every level
+/// is a multiple of the first, which is the one computed while decoding.
+pub const PEAK_LEVELS: [u32; 4] = [256, 1024, 4096, 16384];
+
+const PEAKS_MAGIC: &[u8; 4] = b"ORPK";
+
+/// Min/max pairs over all channels, scaled to i16.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakLevel {
+    pub samples_per_peak: u32,
+    /// Interleaved `min, max` per peak.
+    pub peaks: Vec<i16>,
+}
+
+impl PeakLevel {
+    pub fn len(&self) -> usize {
+        self.peaks.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peaks.is_empty()
+    }
+
+    /// This level with `factor` peaks merged into one.
+    fn downsample(&self, factor: u32) -> PeakLevel {
+        let peaks = self
+            .peaks
+            .chunks(2 * factor as usize)
+            .flat_map(|group| {
+                let min = group.iter().step_by(2).copied().min().unwrap_or(0);
+                let max = group.iter().skip(1).step_by(2).copied().max().unwrap_or(0);
+                [min, max]
+            })
+            .collect();
+        PeakLevel {
+            samples_per_peak: self.samples_per_peak * factor,
+            peaks,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformPeaks {
+    pub sample_rate: u32,
+    /// Decoded frames per channel.
+    pub frames: u64,
+    pub levels: Vec<PeakLevel>,
+}
+
+impl WaveformPeaks {
+    pub fn duration_seconds(&self) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.frames as f64 / self.sample_rate as f64
+    }
+
+    /// Finest level with at most `max_peaks` peaks, or the coarsest one.
+    pub fn level_for(&self, max_peaks: usize) -> Option<&PeakLevel> {
+        self.levels
+            .iter()
+            .find(|level| level.len() <= max_peaks)
+            .or_else(|| self.levels.last())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let peaks: usize = self.levels.iter().map(|l| l.peaks.len()).sum();
+        let mut out = Vec::with_capacity(24 + self.levels.len() * 8 + peaks * 2);
+        out.extend_from_slice(PEAKS_MAGIC);
+        out.extend_from_slice(&PEAKS_FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.sample_rate.to_le_bytes());
+        out.extend_from_slice(&self.frames.to_le_bytes());
+        out.extend_from_slice(&(self.levels.len() as u32).to_le_bytes());
+        for level in &self.levels {
+            out.extend_from_slice(&level.samples_per_peak.to_le_bytes());
+            out.extend_from_slice(&(level.len() as u32).to_le_bytes());
+            for value in &level.peaks {
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    /// Parses `to_bytes` output; files of another format version are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader { bytes, pos: 0 };
+        if reader.take(4)? != PEAKS_MAGIC {
+            return Err("Not a peaks file".to_string());
+        }
+        let version = reader.u32()?;
+        if version != PEAKS_FORMAT_VERSION {
+            return Err(format!("Peaks format version {} is not supported", version));
+        }
+        let sample_rate = reader.u32()?;
+        let frames = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let count = reader.u32()?;
+
+        let mut levels = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let samples_per_peak = reader.u32()?;
+            let len = reader.u32()? as usize;
+            let peaks = reader
+                .take(len * 4)?
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                .collect();
+            levels.push(PeakLevel { samples_per_peak, peaks });
+        }
+        Ok(WaveformPeaks {
+            sample_rate,
+            frames,
+            levels,
+        })
+    }
+
+    /// Cached peaks in `dir`, `None` if missing or unreadable.
+    pub fn read(dir: &Path) -> Option<Self> {
+        let bytes = fs::read(dir.join(PEAKS_FILE)).ok()?;
+        match Self::from_bytes(&bytes) {
+            Ok(peaks) => Some(peaks),
+            Err(e) => {
+                log::warn!("Ignoring peaks in {}: {}", dir.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), String> {
+        let tmp = dir.join(format!("{}.tmp", PEAKS_FILE));
+        fs::write(&tmp, self.to_bytes()).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, dir.join(PEAKS_FILE)).map_err(|e| e.to_string())
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| "Peaks file is truncated".to_string())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// Collects min/max per `samples_per_peak` frames.
+struct PeakAccumulator {
+    samples_per_peak: u32,
+    filled: u32,
+    min: f32,
+    max: f32,
+    peaks: Vec<i16>,
+}
+
+impl PeakAccumulator {
+    fn new(samples_per_peak: u32) -> Self {
+        PeakAccumulator {
+            samples_per_peak,
+            filled: 0,
+            min: f32::MAX,
+            max: f32::MIN,
+            peaks: Vec::new(),
+        }
+    }
+
+    fn push_frame(&mut self, frame: &[f32]) {
+        for &sample in frame {
+            self.min = self.min.min(sample);
+            self.max = self.max.max(sample);
+        }
+        self.filled += 1;
+        if self.filled == self.samples_per_peak {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.filled == 0 {
+            return;
+        }
+        self.peaks.push(to_i16(self.min));
+        self.peaks.push(to_i16(self.max));
+        self.filled = 0;
+        self.min = f32::MAX;
+        self.max = f32::MIN;
+    }
+
+    fn finish(mut self) -> PeakLevel {
+        self.flush();
+        PeakLevel {
+            samples_per_peak: self.samples_per_peak,
+            peaks: self.peaks,
+        }
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+/// Builds all `PEAK_LEVELS` from the finest one.
+fn build_levels(base: PeakLevel) -> Vec<PeakLevel> {
+    let mut levels: Vec<PeakLevel> = PEAK_LEVELS[1..]
+        .iter()
+        .map(|spp| base.downsample(spp / base.samples_per_peak))
+        .collect();
+    levels.insert(0, base);
+    levels
+}
+
+/// A file that records how far it has been read, for progress of formats
+/// that don't state their length.
+struct TrackedFile {
+    file: fs::File,
+    position: Arc<AtomicU64>,
+}
+
+impl Read for TrackedFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.file.read(buf)?;
+        self.position.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+impl Seek for TrackedFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = self.file.seek(pos)?;
+        self.position.store(position, Ordering::Relaxed);
+        Ok(position)
+    }
+}
+
+impl MediaSource for TrackedFile {
+    fn is_seekable(&self) -> bool {
+        self.file.is_seekable()
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.file.byte_len()
+    }
+}
+
+/// Decodes the first audio track of `path` and computes its peaks at every
+/// `PEAK_LEVELS` resolution. `on_progress` gets the decoded fraction in
+/// [0.0, 1.0], measured in frames when the track length is known and in
+/// bytes read otherwise.
+pub fn compute_peaks(path: &Path, mut on_progress: impl FnMut(f64)) -> Result<WaveformPeaks, String> {
+    let source = formats::decodable(path)?;
+    let file = fs::File::open(source.path()).map_err(|e| e.to_string())?;
+    let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let position = Arc::new(AtomicU64::new(0));
+    let tracked = TrackedFile {
+        file,
+        position: position.clone(),
+    };
+    let mut format = open_format_from(source.path(), Box::new(tracked))?;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("No audio track found in {}", path.display()))?;
+    let track_id = track.id;
+    let total_frames = track.codec_params.n_frames.filter(|n| *n > 0);
+
+    let mut decoder = get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported codec: {}", e))?;
+
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut sample_buf: Option<SampleBuffer<f32>> = None;
+    let mut accumulator = PeakAccumulator::new(PEAK_LEVELS[0]);
+    let mut frames = 0u64;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(format!("Failed to read packet: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Corrupt frames are skipped, the rest of the stream is still usable
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Failed to decode packet: {}", e)),
+        };
+
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        let channels = spec.channels.count().max(1);
+
+        let buf = match sample_buf.as_mut() {
+            Some(buf) if buf.capacity() >= decoded.capacity() * channels => buf,
+            _ => sample_buf.insert(SampleBuffer::<f32>::new(decoded.capacity() as u64, spec)),
+        };
+        buf.copy_interleaved_ref(decoded);
+
+        for frame in buf.samples().chunks(channels) {
+            accumulator.push_frame(frame);
+        }
+        frames += (buf.samples().len() / channels) as u64;
+
+        if let Some(total) = total_frames {
+            on_progress((frames as f64 / total as f64).min(1.0));
+        } else if file_size > 0 {
+            // Reads run ahead of decoding by a buffer, close enough for progress
+            on_progress((position.load(Ordering::Relaxed) as f64 / file_size as f64).min(1.0));
+        }
+    }
+
+    if sample_rate == 0 {
+        return Err(format!("Unknown sample rate for {}", path.display()));
+    }
+    on_progress(1.0);
+
+    Ok(WaveformPeaks {
+        sample_rate,
+        frames,
+        levels: build_levels(accumulator.finish()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_level(frames: &[f32]) -> PeakLevel {
+        let mut accumulator = PeakAccumulator::new(PEAK_LEVELS[0]);
+        for sample in frames {
+            accumulator.push_frame(&[*sample]);
+        }
+        accumulator.finish()
+    }
+
+    #[test]
+    fn coarser_levels_merge_min_and_max() {
+        // One loud frame in the second base peak, a partial peak at the end
+        let mut frames = vec![0.0; PEAK_LEVELS[0] as usize * 5 + 10];
+        frames[PEAK_LEVELS[0] as usize + 3] = 1.0;
+        frames[PEAK_LEVELS[0] as usize * 4 + 1] = -0.5;
+
+        let levels = build_levels(base_level(&frames));
+
+        assert_eq!(levels.len(), PEAK_LEVELS.len());
+        assert_eq!(levels[0].len(), 6);
+        assert_eq!(levels[1].samples_per_peak, 1024);
+        assert_eq!(levels[1].peaks, vec![0, i16::MAX, -16384, 0]);
+        assert_eq!(levels[3].peaks, vec![-16384, i16::MAX]);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let peaks = WaveformPeaks {
+            sample_rate: 44_100,
+            frames: 300,
+            levels: build_levels(base_level(&[0.25, -0.75, 0.5])),
+        };
+        let bytes = peaks.to_bytes();
+
+        assert_eq!(WaveformPeaks::from_bytes(&bytes).unwrap(), peaks);
+        assert!(WaveformPeaks::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert_eq!(peaks.level_for(1).unwrap().samples_per_peak, PEAK_LEVELS[0]);
+    }
+
+    #[test]
+    fn tracked_file_follows_reads_and_seeks() {
+        let dir = crate::storage::tests::scratch_dir("peaks-tracked");
+        fs::write(dir.join("audio.bin"), [7u8; 100]).unwrap();
+        let position = Arc::new(AtomicU64::new(0));
+        let mut file = TrackedFile {
+            file: fs::File::open(dir.join("audio.bin")).unwrap(),
+            position: position.clone(),
+        };
+
+        file.read_exact(&mut [0u8; 40]).unwrap();
+        assert_eq!(position.load(Ordering::Relaxed), 40);
+        file.seek(SeekFrom::Start(90)).unwrap();
+        assert_eq!(file.read(&mut [0u8; 40]).unwrap(), 10);
+        assert_eq!(position.load(Ordering::Relaxed), 100);
+        assert_eq!(file.byte_len(), Some(100));
+    }
+}
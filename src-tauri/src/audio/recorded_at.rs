@@ -0,0 +1,387 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Seconds between 1904-01-01 (QuickTime epoch) and 1970-01-01
+const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+// Timestamps outside this range are taken as unset or garbage
+const MIN_YEAR: i64 = 1990;
+const MAX_YEAR: i64 = 2100;
+
+/// When a recording was started, in Unix seconds. Timestamps without a
+/// time zone (tags, BWF, file names) are taken as UTC, which keeps them on
+/// their calendar day in the UTC day buckets of the insights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedAt {
+    pub unix: i64,
+    pub source: RecordedAtSource,
+}
+
+/// Where `RecordedAt` came from, most reliable first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordedAtSource {
+    /// BWF `bext` origination date and time, written by field recorders.
+    Bext,
+    /// MP4/MOV `mvhd` creation time.
+    Mp4,
+    /// ID3 TDRC (or TYER/TDAT/TIME), MP4 ©day, RIFF ICRD.
+    Tag,
+    /// Recorder naming pattern such as `20240305_142210`.
+    FileName,
+    /// File modification time; changes when files are copied or synced.
+    Mtime,
+}
+
+impl RecordedAtSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecordedAtSource::Bext => "bext",
+            RecordedAtSource::Mp4 => "mp4",
+            RecordedAtSource::Tag => "tag",
+            RecordedAtSource::FileName => "fileName",
+            RecordedAtSource::Mtime => "mtime",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "bext" => Some(RecordedAtSource::Bext),
+            "mp4" => Some(RecordedAtSource::Mp4),
+            "tag" => Some(RecordedAtSource::Tag),
+            "fileName" => Some(RecordedAtSource::FileName),
+            "mtime" => Some(RecordedAtSource::Mtime),
+            _ => None,
+        }
+    }
+}
+
+/// Start time of `path` from its metadata, falling back to the file name.
+/// `date_tags` are the (key, value) pairs of date tags symphonia read.
+pub fn detect(path: &Path, date_tags: &[(String, String)]) -> Option<RecordedAt> {
+    let embedded = fs::File::open(path).ok().and_then(|mut file| {
+        bext_origination(&mut file)
+            .map(|unix| (unix, RecordedAtSource::Bext))
+            .or_else(|| mp4_creation_time(&mut file).map(|unix| (unix, RecordedAtSource::Mp4)))
+    });
+    let (unix, source) = embedded
+        .or_else(|| from_date_tags(date_tags).map(|unix| (unix, RecordedAtSource::Tag)))
+        .or_else(|| from_file_name(path).map(|unix| (unix, RecordedAtSource::FileName)))?;
+    Some(RecordedAt { unix, source })
+}
+
+/// Origination date and time of a Broadcast Wave file.
+fn bext_origination<R: Read + Seek>(reader: &mut R) -> Option<i64> {
+    reader.seek(SeekFrom::Start(0)).ok()?;
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).ok()?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return None;
+    }
+
+    loop {
+        let mut chunk = [0u8; 8];
+        reader.read_exact(&mut chunk).ok()?;
+        let size = u32::from_le_bytes(chunk[4..8].try_into().unwrap()) as u64;
+        if &chunk[0..4] == b"bext" {
+            // Description (256), originator (32), reference (32), then
+            // "yyyy-mm-dd" and "hh:mm:ss" with any separators
+            let mut bext = [0u8; 338];
+            reader.read_exact(&mut bext).ok()?;
+            let date = String::from_utf8_lossy(&bext[320..330]).to_string();
+            let time = String::from_utf8_lossy(&bext[330..338]).to_string();
+            return parse_timestamp(&format!("{} {}", date, time));
+        }
+        reader.seek(SeekFrom::Current((size + size % 2) as i64)).ok()?;
+    }
+}
+
+/// `creation_time` of the `mvhd` box of an MP4/MOV file.
+fn mp4_creation_time<R: Read + Seek>(reader: &mut R) -> Option<i64> {
+    let end = reader.seek(SeekFrom::End(0)).ok()?;
+    let mut header = [0u8; 8];
+    reader.seek(SeekFrom::Start(0)).ok()?;
+    reader.read_exact(&mut header).ok()?;
+    if &header[4..8] != b"ftyp" {
+        return None;
+    }
+    let (moov_start, moov_end) = find_box(reader, 0, end, b"moov")?;
+    let (mvhd_start, _) = find_box(reader, moov_start, moov_end, b"mvhd")?;
+
+    reader.seek(SeekFrom::Start(mvhd_start)).ok()?;
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version).ok()?;
+    let created = if version[0] == 1 {
+        let mut value = [0u8; 8];
+        reader.read_exact(&mut value).ok()?;
+        u64::from_be_bytes(value) as i64
+    } else {
+        let mut value = [0u8; 4];
+        reader.read_exact(&mut value).ok()?;
+        u32::from_be_bytes(value) as i64
+    };
+
+    let unix = created - MP4_EPOCH_OFFSET;
+    plausible(unix).then_some(unix)
+}
+
+/// Content range of the first `kind` box between `start` and `end`.
+fn find_box<R: Read + Seek>(reader: &mut R, start: u64, end: u64, kind: &[u8; 4]) -> Option<(u64, u64)> {
+    let mut pos = start;
+    while pos + 8 <= end {
+        reader.seek(SeekFrom::Start(pos)).ok()?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).ok()?;
+        let mut size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+        let mut content = pos + 8;
+        if size == 1 {
+            let mut large = [0u8; 8];
+            reader.read_exact(&mut large).ok()?;
+            size = u64::from_be_bytes(large);
+            content += 8;
+        } else if size == 0 {
+            size = end - pos;
+        }
+        if size < content - pos {
+            return None;
+        }
+        if &header[4..8] == kind {
+            return Some((content, (pos + size).min(end)));
+        }
+        pos += size;
+    }
+    None
+}
+
+/// ID3v2.4 TDRC and MP4 ©day are ISO 8601; ID3v2.3 splits the date into
+/// TYER, TDAT (DDMM) and TIME (HHMM). Only values with at least a day count.
+pub fn from_date_tags(tags: &[(String, String)]) -> Option<i64> {
+    let value = |key: &str| tags.iter().find(|(k, _)| k == key).map(|(_, v)| v.trim());
+    if let (Some(year), Some(date)) = (value("TYER"), value("TDAT")) {
+        let time = value("TIME").unwrap_or("0000");
+        let combined = format!("{}-{}-{} {}:{}", year, date.get(2..4)?, date.get(0..2)?, time.get(0..2)?, time.get(2..4)?);
+        if let Some(unix) = parse_timestamp(&combined) {
+            return Some(unix);
+        }
+    }
+    tags.iter().find_map(|(_, v)| parse_timestamp(v))
+}
+
+/// Parses `YYYY-MM-DD[ HH:MM[:SS]]` with any single-character separators,
+/// or the same fields without separators, plus an optional `Z` or
+/// `+HH:MM` offset.
+pub fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let runs = digit_runs(value);
+    let mut fields: Vec<&str> = Vec::new();
+    let mut end = 0;
+    for (start, run) in &runs {
+        // Fields start the value and are one separator apart; a sign after
+        // the time starts the zone offset
+        let gap = &value[end..*start];
+        let separated = if fields.is_empty() { gap.is_empty() } else { gap.len() == 1 };
+        let in_time = fields.len() >= 4 || (fields.len() >= 2 && fields[0].len() > 4);
+        if !separated || fields.len() >= 6 || (in_time && (gap == "+" || gap == "-")) {
+            break;
+        }
+        fields.push(run);
+        end = start + run.len();
+    }
+    // Compact forms like 20240305T142210
+    let joined: String = fields.concat();
+    let fields: Vec<&str> = match fields.first().map(|f| f.len()) {
+        Some(4) => fields,
+        Some(8) | Some(12) | Some(14) => split_compact(&joined)?,
+        _ => return None,
+    };
+    if fields.len() < 3 || fields[1..].iter().any(|f| f.len() != 2) {
+        return None;
+    }
+
+    let number = |i: usize| fields.get(i).and_then(|f| f.parse::<u32>().ok()).unwrap_or(0);
+    let unix = unix_time(fields[0].parse().ok()?, number(1), number(2), number(3), number(4), number(5))?;
+    Some(unix - zone_offset(&value[end..]).unwrap_or(0))
+}
+
+fn split_compact(digits: &str) -> Option<Vec<&str>> {
+    let mut fields = vec![digits.get(0..4)?];
+    let mut pos = 4;
+    while pos < digits.len() {
+        fields.push(digits.get(pos..pos + 2)?);
+        pos += 2;
+    }
+    Some(fields)
+}
+
+/// Offset in seconds of a trailing `Z`, `+HH:MM` or `-HHMM`; fractional
+/// seconds in front of it are skipped.
+fn zone_offset(rest: &str) -> Option<i64> {
+    let rest = rest.trim_start_matches(|c: char| c == '.' || c == ',' || c.is_ascii_digit()).trim();
+    let sign = match rest.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = rest[1..].chars().filter(|c| c.is_ascii_digit()).collect();
+    let hours: i64 = digits.get(0..2)?.parse().ok()?;
+    let minutes: i64 = digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Recorder naming patterns: `20240305_142210`, `2024-03-05 14-22-10`,
+/// `Recording_20240305-1422` and Sony/Zoom style `240305_1422`.
+pub fn from_file_name(path: &Path) -> Option<i64> {
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+    let runs = digit_runs(&stem);
+
+    for i in 0..runs.len() {
+        // Runs separated by one of these characters belong together
+        let group: Vec<&str> = runs[i..]
+            .iter()
+            .enumerate()
+            .take_while(|(j, (start, _))| {
+                *j == 0 || {
+                    let (prev_start, prev) = &runs[i + j - 1];
+                    let gap = &stem[prev_start + prev.len()..*start];
+                    gap.len() == 1 && " _-.T:".contains(gap)
+                }
+            })
+            .map(|(_, (_, run))| *run)
+            .collect();
+
+        let candidate = match group.iter().map(|r| r.len()).collect::<Vec<_>>().as_slice() {
+            [14, ..] | [8] => group[0].to_string(),
+            [8, 6 | 4, ..] => group[..2].concat(),
+            [4, 2, 2, 2, 2, 2, ..] => group[..6].concat(),
+            [4, 2, 2, 2, 2, ..] => group[..5].concat(),
+            [4, 2, 2, 6 | 4, ..] => group[..4].concat(),
+            [4, 2, 2, ..] => group[..3].concat(),
+            // Two-digit years only with a time, on their own they are too ambiguous
+            [6, 6 | 4, ..] => format!("20{}", group[..2].concat()),
+            _ => continue,
+        };
+        if let Some(unix) = parse_timestamp(&candidate) {
+            return Some(unix);
+        }
+    }
+    None
+}
+
+/// Byte offset and text of each run of ASCII digits.
+fn digit_runs(value: &str) -> Vec<(usize, &str)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, c) in value.char_indices().chain(std::iter::once((value.len(), ' '))) {
+        match (c.is_ascii_digit(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push((s, &value[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+fn plausible(unix: i64) -> bool {
+    let year = 1970 + unix.div_euclid(31_556_952);
+    (MIN_YEAR..=MAX_YEAR).contains(&year)
+}
+
+/// Unix time of a UTC civil date and time, `None` if any field is out of range.
+fn unix_time(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Option<i64> {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if !(MIN_YEAR..=MAX_YEAR).contains(&year) || day == 0 || day > days_in_month || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    // Days since 1970-01-01 (Howard Hinnant's days_from_civil)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Some(days * 86_400 + hour as i64 * 3600 + minute as i64 * 60 + second as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    // 2024-03-05 14:22:10 UTC
+    const STANDUP: i64 = 1_709_648_530;
+
+    #[test]
+    fn parses_tag_timestamps() {
+        assert_eq!(parse_timestamp("2024-03-05T14:22:10"), Some(STANDUP));
+        assert_eq!(parse_timestamp("2024-03-05T16:22:10+02:00"), Some(STANDUP));
+        assert_eq!(parse_timestamp("2024-03-05T14:22:10.250Z"), Some(STANDUP));
+        assert_eq!(parse_timestamp("2024:03:05 14:22:10"), Some(STANDUP));
+        assert_eq!(parse_timestamp("20240305T092210-0500"), Some(STANDUP));
+        assert_eq!(parse_timestamp("2024-03-05"), Some(STANDUP - 14 * 3600 - 22 * 60 - 10));
+        assert_eq!(parse_timestamp("2024"), None);
+        assert_eq!(parse_timestamp("2024-02-30"), None);
+
+        let id3v23 = [
+            ("TYER".to_string(), "2024".to_string()),
+            ("TDAT".to_string(), "0503".to_string()),
+            ("TIME".to_string(), "1422".to_string()),
+        ];
+        assert_eq!(from_date_tags(&id3v23), Some(STANDUP - 10));
+    }
+
+    #[test]
+    fn parses_recorder_file_names() {
+        let name = |n: &str| from_file_name(&PathBuf::from(format!("/rec/{}.m4a", n)));
+
+        assert_eq!(name("20240305_142210"), Some(STANDUP));
+        assert_eq!(name("Recording 2024-03-05 14-22-10"), Some(STANDUP));
+        assert_eq!(name("REC_20240305-142210 (1)"), Some(STANDUP));
+        assert_eq!(name("240305_1422"), Some(STANDUP - 10));
+        assert_eq!(name("Standup 2024-03-05"), Some(STANDUP - 14 * 3600 - 22 * 60 - 10));
+        assert_eq!(name("ZOOM0001"), None);
+        assert_eq!(name("Track 12"), None);
+    }
+
+    #[test]
+    fn reads_bext_and_mvhd_dates() {
+        let mut bext = vec![0u8; 338];
+        bext[320..338].copy_from_slice(b"2024-03-0514:22:10");
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF\0\0\0\0WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&[0u8; 16]);
+        wav.extend_from_slice(b"bext");
+        wav.extend_from_slice(&(bext.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&bext);
+        assert_eq!(bext_origination(&mut Cursor::new(&wav)), Some(STANDUP));
+
+        let mut mvhd = vec![0u8; 4];
+        mvhd.extend_from_slice(&((STANDUP + MP4_EPOCH_OFFSET) as u32).to_be_bytes());
+        let mut mp4 = Vec::new();
+        mp4.extend_from_slice(&16u32.to_be_bytes());
+        mp4.extend_from_slice(b"ftypM4A \0\0\0\0");
+        mp4.extend_from_slice(&(16 + mvhd.len() as u32).to_be_bytes());
+        mp4.extend_from_slice(b"moov");
+        mp4.extend_from_slice(&(8 + mvhd.len() as u32).to_be_bytes());
+        mp4.extend_from_slice(b"mvhd");
+        mp4.extend_from_slice(&mvhd);
+        assert_eq!(mp4_creation_time(&mut Cursor::new(&mp4)), Some(STANDUP));
+        assert_eq!(mp4_creation_time(&mut Cursor::new(&wav)), None);
+    }
+}
@@ -1 +1,2 @@
-pub mod transcription;
\ No newline at end of file
+pub mod transcription;
+pub mod waveform;
@@ -1,41 +1,77 @@
-use async_openai::{
-    config::OpenAIConfig,
-    error::OpenAIError,
-    types::audio::{
-        AudioResponseFormat,
-        CreateTranscriptionRequestArgs,
-        TimestampGranularity,
-    },
-    types::chat::{ChatCompletionRequestMessage, ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageContent, CreateChatCompletionRequestArgs},
-    Client,
-};
 use tauri_plugin_http::reqwest::Error as ReqwestError;
 use serde::{Deserialize, Serialize};
-use serde::de::DeserializeOwned;
 use serde_json;
 use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
-use tauri::{AppHandle, Manager};
-use md5;
+use tauri::AppHandle;
 use log::{info, warn};
+use crate::audio::formats;
+use crate::insights;
+use crate::library;
+use crate::retry;
+use crate::settings;
 use crate::storage;
+use crate::transcription;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WordTimestamp {
     pub word: String,
     pub start: f32,
     pub end: f32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentTimestamp {
+    pub text: String,
+    pub start: f32,
+    pub end: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transcript {
     pub text: String,
     pub words: Vec<WordTimestamp>,
+    #[serde(default)]
+    pub segments: Vec<SegmentTimestamp>,
     pub duration: f32,
     pub language: String,
 }
 
+/// Bump when the layout of `transcript.json` changes.
+pub const TRANSCRIPT_FORMAT_VERSION: u32 = 1;
+
+/// Contents of `transcript.json` in the managed directory.
+///
+/// `version` 0 is never written: it marks transcripts loaded from a plain
+/// `transcript.txt`, which carry text only (no words, segments or language).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredTranscript {
+    pub version: u32,
+    /// True once the text was edited by the user; timings still refer to the
+    /// original recognition output.
+    #[serde(default)]
+    pub edited: bool,
+    #[serde(flatten)]
+    pub transcript: Transcript,
+}
+
+impl StoredTranscript {
+    fn from_plain_text(text: String) -> Self {
+        Self {
+            version: 0,
+            edited: false,
+            transcript: Transcript {
+                text,
+                words: Vec::new(),
+                segments: Vec::new(),
+                duration: 0.0,
+                language: "unknown".to_string(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RecommendedAction {
     pub title: String,
@@ -49,24 +85,13 @@ pub struct TranscriptInsights {
     pub topics: Option<Vec<String>>,
 }
 
-fn is_supported_format(path: &PathBuf) -> bool {
-    if let Some(ext) = path.extension() {
-        let ext = ext.to_string_lossy().to_lowercase();
-        matches!(ext.as_str(), "mp3" | "m4a" | "wav" | "flac" | "ogg" | "aac")
-    } else {
-        false
-    }
-}
-
 #[derive(Debug, Error)]
 pub enum TranscriptionError {
-    #[error("Missing API key: OPENAI_API_KEY environment variable not set")]
+    #[error("Missing API key: set one in settings or the OPENAI_API_KEY environment variable")]
     MissingApiKey,
     #[error("File not found: {0}")]
     FileNotFound(String),
-    #[error("File too large: {0} (max 25 MB)")]
-    FileTooLarge(String),
-    #[error("Unsupported audio format. Supported: mp3, m4a, wav, flac, ogg, aac")]
+    #[error("Unsupported audio format. Supported: {}", formats::supported_list())]
     UnsupportedFormat,
     #[error("OpenAI API error: {0}")]
     ApiError(String),
@@ -80,6 +105,22 @@ pub enum TranscriptionError {
     FileError(String),
     #[error("Invalid request: {0}")]
     RequestError(String),
+    #[error("Settings error: {0}")]
+    SettingsError(String),
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+    #[error("Failed to decode audio: {0}")]
+    DecodeError(String),
+    #[error("Rate limited by API: {0}")]
+    RateLimited(String),
+    #[error("Authentication failed, check the API key: {0}")]
+    AuthError(String),
+    #[error("API quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+    #[error("Gave up after {attempts} attempts: {last_error}")]
+    RetriesExhausted { attempts: u32, last_error: String },
 }
 
 impl From<std::io::Error> for TranscriptionError {
@@ -93,7 +134,7 @@ impl From<std::io::Error> for TranscriptionError {
 
 impl From<async_openai::error::OpenAIError> for TranscriptionError {
     fn from(err: async_openai::error::OpenAIError) -> Self {
-        TranscriptionError::ApiError(err.to_string())
+        retry::into_transcription_error(err)
     }
 }
 
@@ -108,6 +149,7 @@ pub async fn transcribe_audio(app: AppHandle, path: PathBuf) -> Result<Transcrip
     transcribe_audio_inner(app, path).await.map_err(|e| e.to_string())
 }
 
+// Blocks until every file is done; prefer `jobs::enqueue_transcriptions`
 #[tauri::command]
 pub async fn transcribe_audio_batch(app: AppHandle, paths: Vec<PathBuf>) -> Result<Vec<Result<Transcript, String>>, String> {
     let mut results = Vec::new();
@@ -118,75 +160,72 @@ pub async fn transcribe_audio_batch(app: AppHandle, paths: Vec<PathBuf>) -> Resu
     Ok(results)
 }
 
-async fn transcribe_audio_inner(app: AppHandle, path: PathBuf) -> Result<Transcript, TranscriptionError> {
+pub(crate) async fn transcribe_audio_inner(app: AppHandle, path: PathBuf) -> Result<Transcript, TranscriptionError> {
     info!("Transcribing audio file: {:?}", path);
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .map_err(|_| TranscriptionError::MissingApiKey)?;
-
-    let config = OpenAIConfig::new().with_api_key(&api_key);
-    let client = Client::with_config(config);
-    
-    // Validate file size (25 MB limit)
-    let metadata = std::fs::metadata(&path)?;
-    if metadata.len() > 25 * 1024 * 1024 {
-        return Err(TranscriptionError::FileTooLarge(path.display().to_string()));
-    }
+    let settings = settings::load_settings(&app)
+        .map_err(|e| TranscriptionError::SettingsError(e))?;
+    let provider = transcription::provider_from_settings(&settings)?;
 
     // Validate file extension
-    if !is_supported_format(&path) {
+    if !formats::is_supported(&path) {
         return Err(TranscriptionError::UnsupportedFormat);
     }
     
-    // Ensure audio is copied to managed storage and get managed directory
+    // Ensure audio is in managed storage (or referenced) and get managed directory
     let managed_dir = storage::ensure_audio_dir(&app, &path)
         .map_err(|e| TranscriptionError::FileError(e))?;
+    let audio_path = storage::audio_source(&managed_dir, &path)
+        .map_err(|e| TranscriptionError::FileNotFound(e))?;
     
-    // Determine extension of original file
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("bin");
-    let audio_path = managed_dir.join(format!("audio.{}", ext));
-    
-    // Try whisper-1 model which supports verbose_json with word timestamps
-    // If that fails, fall back to gpt-4o-transcribe with json format (no word timestamps)
-    let request = CreateTranscriptionRequestArgs::default()
-        .file(&audio_path)
-        .model("whisper-1")
-        .response_format(AudioResponseFormat::VerboseJson)
-        .timestamp_granularities(&[TimestampGranularity::Word])
-        .build()
-        .map_err(|e: OpenAIError| TranscriptionError::RequestError(e.to_string()))?;
+    info!("Using transcription provider: {}", provider.name());
+    let transcript = provider.transcribe(&audio_path).await?;
     
-    let response = client
-        .audio()
-        .transcription()
-        .create_verbose_json(request)
-        .await?;
-    
-    // Extract words from response (words field is available in verbose_json)
-    let words = response
-        .words
-        .unwrap_or_default()
-        .into_iter()
-        .map(|w| WordTimestamp {
-            word: w.word,
-            start: w.start,
-            end: w.end,
-        })
-        .collect();
+    // Save structured transcript plus the plain .txt other commands read
+    let stored = StoredTranscript {
+        version: TRANSCRIPT_FORMAT_VERSION,
+        edited: false,
+        transcript,
+    };
+    write_transcript_files(&managed_dir, &stored)?;
+    let info = storage::TranscriptionInfo {
+        provider: provider.name().to_string(),
+        model: provider.model().to_string(),
+        language: stored.transcript.language.clone(),
+        transcribed_at: crate::now_unix(),
+        edited_at: None,
+    };
+    if let Err(e) = storage::update_manifest(&managed_dir, |m| m.transcription = Some(info)) {
+        warn!("Failed to update manifest for {:?}: {}", path, e);
+    }
+    library::record_transcript(&app, &path, &stored.transcript.language, stored.transcript.duration as f64);
     
-    // Save transcript as .txt file in managed directory
-    let transcript_path = managed_dir.join("transcript.txt");
-    std::fs::write(&transcript_path, &response.text)
+    Ok(stored.transcript)
+}
+
+fn write_transcript_files(managed_dir: &Path, stored: &StoredTranscript) -> Result<(), TranscriptionError> {
+    let json = serde_json::to_string_pretty(stored)
         .map_err(|e| TranscriptionError::SaveError(e.to_string()))?;
-    
-    Ok(Transcript {
-        text: response.text,
-        words,
-        duration: response.duration,
-        language: response.language,
-    })
+    std::fs::write(managed_dir.join("transcript.json"), json)
+        .map_err(|e| TranscriptionError::SaveError(e.to_string()))?;
+    std::fs::write(managed_dir.join("transcript.txt"), &stored.transcript.text)
+        .map_err(|e| TranscriptionError::SaveError(e.to_string()))?;
+    Ok(())
+}
+
+pub(crate) fn read_transcript_json(managed_dir: &Path) -> Option<StoredTranscript> {
+    let json_path = managed_dir.join("transcript.json");
+    let content = std::fs::read_to_string(&json_path).ok()?;
+    match serde_json::from_str::<StoredTranscript>(&content) {
+        Ok(stored) if stored.version <= TRANSCRIPT_FORMAT_VERSION => Some(stored),
+        Ok(stored) => {
+            warn!("Ignoring {:?}: version {} is newer than supported", json_path, stored.version);
+            None
+        }
+        Err(e) => {
+            warn!("Failed to parse {:?}: {}", json_path, e);
+            None
+        }
+    }
 }
 
 #[tauri::command]
@@ -200,37 +239,62 @@ pub async fn save_export(path: PathBuf, content: String) -> Result<(), String> {
 }
 
 async fn save_transcript_inner(app: AppHandle, path: PathBuf, text: String) -> Result<(), TranscriptionError> {
-    // Ensure audio is copied to managed storage and get managed directory
-    let managed_dir = storage::ensure_audio_dir(&app, &path)
+    // Only derived files are written, the audio isn't needed
+    let managed_dir = storage::ensure_managed_dir(&app, &path)
         .map_err(|e| TranscriptionError::FileError(e))?;
     
+    // Keep transcript.json in sync, timings are kept but flagged as edited
+    if let Some(mut stored) = read_transcript_json(&managed_dir) {
+        let changed = stored.transcript.text != text;
+        if changed {
+            stored.transcript.text = text;
+            stored.edited = true;
+        }
+        write_transcript_files(&managed_dir, &stored)?;
+        let written = storage::update_manifest(&managed_dir, |m| match m.transcription.as_mut() {
+            Some(info) if changed => info.edited_at = Some(crate::now_unix()),
+            _ => {}
+        });
+        if let Err(e) = written {
+            warn!("Failed to update manifest for {:?}: {}", path, e);
+        }
+        return Ok(());
+    }
+    
     // Write transcript.txt in managed directory
     let transcript_path = managed_dir.join("transcript.txt");
     std::fs::write(&transcript_path, text)
         .map_err(|e| TranscriptionError::SaveError(e.to_string()))?;
+    storage::touch_manifest(&managed_dir);
     
     Ok(())
 }
 
 #[tauri::command]
-pub async fn read_transcript(app: AppHandle, path: PathBuf) -> Result<String, String> {
+pub async fn read_transcript(app: AppHandle, path: PathBuf) -> Result<StoredTranscript, String> {
     read_transcript_inner(app, path).await.map_err(|e| e.to_string())
 }
 
-async fn read_transcript_inner(app: AppHandle, path: PathBuf) -> Result<String, TranscriptionError> {
-    // Get managed directory (copy audio if needed)
-    let managed_dir = storage::ensure_audio_dir(&app, &path)
+pub(crate) async fn read_transcript_inner(app: AppHandle, path: PathBuf) -> Result<StoredTranscript, TranscriptionError> {
+    // Reading never creates the directory or touches the audio
+    let managed_dir = storage::get_managed_path(&app, &path)
         .map_err(|e| TranscriptionError::FileError(e))?;
     
+    // Structured transcript has everything, prefer it
+    if let Some(stored) = read_transcript_json(&managed_dir) {
+        info!("Read structured transcript v{} with {} words", stored.version, stored.transcript.words.len());
+        return Ok(stored);
+    }
+    
     let managed_transcript_path = managed_dir.join("transcript.txt");
     
-    // Check if managed transcript exists
+    // Older entries only have transcript.txt
     if managed_transcript_path.exists() {
         info!("Reading transcript from managed location: {:?}", managed_transcript_path);
         let content = std::fs::read_to_string(&managed_transcript_path)
             .map_err(|e| TranscriptionError::FileError(e.to_string()))?;
         info!("Read transcript of length {} chars", content.len());
-        return Ok(content);
+        return Ok(StoredTranscript::from_plain_text(content));
     }
     
     // Managed transcript not found, check sidecar transcript
@@ -241,10 +305,13 @@ async fn read_transcript_inner(app: AppHandle, path: PathBuf) -> Result<String,
         let content = std::fs::read_to_string(&sidecar_transcript_path)
             .map_err(|e| TranscriptionError::FileError(e.to_string()))?;
         // Copy to managed location
+        storage::ensure_managed_dir(&app, &path)
+            .map_err(|e| TranscriptionError::FileError(e))?;
         std::fs::write(&managed_transcript_path, &content)
             .map_err(|e| TranscriptionError::SaveError(e.to_string()))?;
+        storage::touch_manifest(&managed_dir);
         info!("Migrated transcript to managed storage");
-        return Ok(content);
+        return Ok(StoredTranscript::from_plain_text(content));
     }
     
     // No transcript found
@@ -253,26 +320,48 @@ async fn read_transcript_inner(app: AppHandle, path: PathBuf) -> Result<String,
 }
 
 #[tauri::command]
-pub async fn summarize_transcript(app: AppHandle, text: String) -> Result<String, String> {
-    summarize_transcript_inner(app, text).await.map_err(|e| e.to_string())
+pub async fn summarize_transcript(app: AppHandle, text: String, path: Option<PathBuf>) -> Result<String, String> {
+    summarize_transcript_inner(app, text, path).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn recommend_actions(text: String) -> Result<Vec<RecommendedAction>, String> {
-    recommend_actions_inner(text).await.map_err(|e| e.to_string())
+pub async fn recommend_actions(app: AppHandle, text: String, path: Option<PathBuf>) -> Result<Vec<RecommendedAction>, String> {
+    let (hash, managed_dir, mut insights) = stored_insights(&app, &text, path.as_deref());
+    if let Some(actions) = insights.actions.clone() {
+        info!("Actions cache hit for hash: {}", hash);
+        return Ok(actions);
+    }
+
+    let engine = insights::engine(&app, path.as_deref()).map_err(|e| e.to_string())?;
+    let actions = engine.actions(&text).await.map_err(|e| e.to_string())?;
+    insights.actions = Some(actions.clone());
+    store_insights(&app, &hash, managed_dir.as_deref(), &insights, Some(engine.model()));
+    Ok(actions)
 }
 
 #[tauri::command]
-pub async fn extract_key_topics(text: String) -> Result<Vec<String>, String> {
-    extract_key_topics_inner(text).await.map_err(|e| e.to_string())
+pub async fn extract_key_topics(app: AppHandle, text: String, path: Option<PathBuf>) -> Result<Vec<String>, String> {
+    let (hash, managed_dir, mut insights) = stored_insights(&app, &text, path.as_deref());
+    if let Some(topics) = insights.topics.clone() {
+        info!("Topics cache hit for hash: {}", hash);
+        return Ok(topics);
+    }
+
+    let engine = insights::engine(&app, path.as_deref()).map_err(|e| e.to_string())?;
+    let topics = engine.topics(&text).await.map_err(|e| e.to_string())?;
+    insights.topics = Some(topics.clone());
+    store_insights(&app, &hash, managed_dir.as_deref(), &insights, Some(engine.model()));
+    Ok(topics)
 }
 
 #[tauri::command]
-pub async fn get_transcript_insights(app: AppHandle, text: String) -> Result<TranscriptInsights, String> {
-    get_transcript_insights_inner(app, text).await.map_err(|e| e.to_string())
+pub async fn get_transcript_insights(app: AppHandle, text: String, path: Option<PathBuf>) -> Result<TranscriptInsights, String> {
+    get_transcript_insights_inner(app, text, path.as_deref()).await.map_err(|e| e.to_string())
 }
 
-async fn summarize_transcript_inner(app: AppHandle, text: String) -> Result<String, TranscriptionError> {
+/// `path` is the recording the transcript belongs to; it selects the chat
+/// provider configured for its library and where the insights are stored.
+async fn summarize_transcript_inner(app: AppHandle, text: String, path: Option<PathBuf>) -> Result<String, TranscriptionError> {
     // Validate input
     if text.trim().is_empty() {
         return Err(TranscriptionError::TranscriptionFailed(
@@ -280,13 +369,9 @@ async fn summarize_transcript_inner(app: AppHandle, text: String) -> Result<Stri
         ));
     }
 
-    // Normalize text for consistent hashing (trim, collapse whitespace)
-    let normalized_text = text.trim();
-    // Compute hash of normalized transcript text for caching
-    let hash = format!("{:x}", md5::compute(normalized_text));
-    
-    // Read existing cache (if any)
-    let existing = read_cache(&app, &hash).await?;
+    let hash = storage::transcript_hash(&text);
+    let managed_dir = insights_dir(&app, path.as_deref());
+    let existing = load_insights(&app, &hash, managed_dir.as_deref());
     
     // Check if summary exists in cache
     if let Some(ref cached) = existing {
@@ -300,7 +385,8 @@ async fn summarize_transcript_inner(app: AppHandle, text: String) -> Result<Stri
     info!("Summary cache miss for hash: {}", hash);
     
     // Cache miss, call API
-    let summary = summarize_transcript_api(text.clone()).await?;
+    let engine = insights::engine(&app, path.as_deref())?;
+    let summary = engine.summary(&text).await?;
     
     // Merge with existing cache (preserve actions/topics if they exist)
     let insights = match existing {
@@ -319,197 +405,74 @@ async fn summarize_transcript_inner(app: AppHandle, text: String) -> Result<Stri
         }
     };
     
-    let _ = write_cache(&app, &hash, &insights).await;
+    store_insights(&app, &hash, managed_dir.as_deref(), &insights, Some(engine.model()));
     
     Ok(summary)
 }
 
-async fn read_cache(app: &AppHandle, hash: &str) -> Result<Option<TranscriptInsights>, TranscriptionError> {
-    let cache_dir = app.path().local_data_dir().map_err(|e| {
-        TranscriptionError::FileError(format!("Failed to get local data dir: {}", e))
-    })?;
-    let summaries_dir = cache_dir.join("summaries");
-    let cache_file = summaries_dir.join(format!("{}.txt", hash));
-    
-    if !cache_file.exists() {
-        info!("Cache file does not exist: {:?}", cache_file);
-        return Ok(None);
-    }
-    
-    info!("Reading cache file: {:?}", cache_file);
-    match std::fs::read_to_string(&cache_file) {
-        Ok(content) => {
-            // Try to parse as JSON first
-            match serde_json::from_str::<TranscriptInsights>(&content) {
-                Ok(insights) => {
-                    info!("Successfully parsed JSON cache for hash: {}", hash);
-                    Ok(Some(insights))
-                },
-                Err(e) => {
-                    warn!("Failed to parse JSON cache for hash: {}: {}", hash, e);
-                    // Old format: plain text summary
-                    info!("Trying old plain text format for hash: {}", hash);
-                    Ok(Some(TranscriptInsights {
-                        summary: Some(content),
-                        actions: None,
-                        topics: None,
-                    }))
-                }
-            }
-        }
-        Err(e) => {
-            warn!("Failed to read cache file for hash: {}: {}", hash, e);
-            Ok(None) // If can't read, treat as cache miss
-        }
-    }
+/// Managed directory to keep insights for `path` in, if the recording has one.
+fn insights_dir(app: &AppHandle, path: Option<&Path>) -> Option<PathBuf> {
+    let path = path?.to_path_buf();
+    storage::get_managed_path(app, &path).ok().filter(|dir| dir.is_dir())
 }
 
-async fn write_cache(app: &AppHandle, hash: &str, insights: &TranscriptInsights) -> Result<(), TranscriptionError> {
-    info!("Writing insights cache for hash: {}", hash);
-    let cache_dir = app.path().local_data_dir().map_err(|e| {
-        TranscriptionError::FileError(format!("Failed to get local data dir: {}", e))
-    })?;
-    let summaries_dir = cache_dir.join("summaries");
-    
-    // Create directory if it doesn't exist
-    if !summaries_dir.exists() {
-        info!("Creating cache directory: {:?}", summaries_dir);
-        std::fs::create_dir_all(&summaries_dir).map_err(|e| {
-            TranscriptionError::FileError(format!("Failed to create cache directory: {}", e))
-        })?;
+/// Insights for the transcript with `hash`: the recording's own first, then
+/// the global cache. Cache hits are copied into the recording's directory.
+fn load_insights(app: &AppHandle, hash: &str, managed_dir: Option<&Path>) -> Option<TranscriptInsights> {
+    if let Some(found) = managed_dir.and_then(|dir| storage::read_recording_insights(dir, hash)) {
+        info!("Read insights for hash {} from {:?}", hash, managed_dir);
+        return Some(found);
     }
-    
-    let cache_file = summaries_dir.join(format!("{}.txt", hash));
-    info!("Writing cache to: {:?}", cache_file);
-    let json = serde_json::to_string_pretty(insights)
-        .map_err(|e| TranscriptionError::FileError(format!("Failed to serialize insights: {}", e)))?;
-    std::fs::write(&cache_file, json).map_err(|e| {
-        TranscriptionError::FileError(format!("Failed to write cache file: {}", e))
-    })?;
-    info!("Cache written successfully for hash: {}", hash);
-    
-    Ok(())
-}
-
-async fn summarize_transcript_api(text: String) -> Result<String, TranscriptionError> {
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .map_err(|_| TranscriptionError::MissingApiKey)?;
-
-    let config = OpenAIConfig::new().with_api_key(&api_key);
-    let client = Client::with_config(config);
-
-    let prompt = format!(
-        "Summarize the following transcript in 3‑5 bullet points. Focus on key points, decisions, and action items. Transcript:\n\n{}",
-        text
-    );
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model("gpt-3.5-turbo")
-        .messages(vec![ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
-            content: ChatCompletionRequestUserMessageContent::Text(prompt),
-            name: None,
-        })])
-        .build()
-        .map_err(|e| TranscriptionError::RequestError(e.to_string()))?;
-
-    let response = client
-        .chat()
-        .create(request)
-        .await
-        .map_err(|e| TranscriptionError::ApiError(e.to_string()))?;
 
-    response
-        .choices
-        .first()
-        .and_then(|choice| choice.message.content.clone())
-        .ok_or_else(|| TranscriptionError::TranscriptionFailed("No content in response".to_string()))
+    let cache_dir = storage::get_insights_cache_dir(app).ok()?;
+    let cached = storage::read_cached_insights(&cache_dir, hash)?;
+    info!("Insights cache hit for hash: {}", hash);
+    if let Some(dir) = managed_dir {
+        write_recording_insights(dir, hash, &cached, None);
+    }
+    Some(cached)
 }
 
-async fn recommend_actions_inner(text: String) -> Result<Vec<RecommendedAction>, TranscriptionError> {
-    if text.trim().is_empty() {
-        return Err(TranscriptionError::TranscriptionFailed(
-            "Transcript text is empty".to_string(),
-        ));
+/// Saves insights with the recording and in the global cache. Failures are
+/// logged only; the insights are still returned to the caller.
+fn store_insights(app: &AppHandle, hash: &str, managed_dir: Option<&Path>, insights: &TranscriptInsights, model: Option<&str>) {
+    if let Some(dir) = managed_dir {
+        write_recording_insights(dir, hash, insights, model);
     }
+    let cached = storage::get_insights_cache_dir(app)
+        .and_then(|cache_dir| storage::write_cached_insights(&cache_dir, hash, insights));
+    if let Err(e) = cached {
+        warn!("Failed to write insights cache for hash {}: {}", hash, e);
+    }
+}
 
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .map_err(|_| TranscriptionError::MissingApiKey)?;
-
-    let config = OpenAIConfig::new().with_api_key(&api_key);
-    let client = Client::with_config(config);
-
-    let prompt = format!(
-        "From the transcript below, generate 3-5 concise follow-up action items. Each title should be <= 7 words, description <= 16 words. Respond ONLY with a JSON array of objects with keys 'title' and 'description'. No extra text. Transcript:\n\n{}",
-        text
-    );
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model("gpt-3.5-turbo")
-        .messages(vec![ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
-            content: ChatCompletionRequestUserMessageContent::Text(prompt),
-            name: None,
-        })])
-        .build()
-        .map_err(|e| TranscriptionError::RequestError(e.to_string()))?;
-
-    let response = client
-        .chat()
-        .create(request)
-        .await
-        .map_err(|e| TranscriptionError::ApiError(e.to_string()))?;
-
-    let content = response
-        .choices
-        .first()
-        .and_then(|choice| choice.message.content.clone())
-        .ok_or_else(|| TranscriptionError::TranscriptionFailed("No content in response".to_string()))?;
-
-    parse_json_array::<Vec<RecommendedAction>>(&content)
+/// Hash of `text`, where its insights are kept and what is stored so far, for
+/// commands that generate a single field on its own.
+fn stored_insights(app: &AppHandle, text: &str, path: Option<&Path>) -> (String, Option<PathBuf>, TranscriptInsights) {
+    let hash = storage::transcript_hash(text);
+    let managed_dir = insights_dir(app, path);
+    let insights = load_insights(app, &hash, managed_dir.as_deref()).unwrap_or(TranscriptInsights {
+        summary: None,
+        actions: None,
+        topics: None,
+    });
+    (hash, managed_dir, insights)
 }
 
-async fn extract_key_topics_inner(text: String) -> Result<Vec<String>, TranscriptionError> {
-    if text.trim().is_empty() {
-        return Err(TranscriptionError::TranscriptionFailed(
-            "Transcript text is empty".to_string(),
-        ));
+fn write_recording_insights(dir: &Path, hash: &str, insights: &TranscriptInsights, model: Option<&str>) {
+    let stored = storage::StoredInsights {
+        version: storage::INSIGHTS_FORMAT_VERSION,
+        transcript_hash: hash.to_string(),
+        generated_at: crate::now_unix(),
+        model: model.map(str::to_string),
+        insights: insights.clone(),
+    };
+    if let Err(e) = storage::write_recording_insights(dir, &stored) {
+        warn!("Failed to write insights into {:?}: {}", dir, e);
     }
-
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .map_err(|_| TranscriptionError::MissingApiKey)?;
-
-    let config = OpenAIConfig::new().with_api_key(&api_key);
-    let client = Client::with_config(config);
-
-    let prompt = format!(
-        "Extract 6-10 key topics from the transcript below. Return short tags (1-3 words), no sentences, no verbs. Respond ONLY with a JSON array of strings. No extra text. Transcript:\n\n{}",
-        text
-    );
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model("gpt-3.5-turbo")
-        .messages(vec![ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
-            content: ChatCompletionRequestUserMessageContent::Text(prompt),
-            name: None,
-        })])
-        .build()
-        .map_err(|e| TranscriptionError::RequestError(e.to_string()))?;
-
-    let response = client
-        .chat()
-        .create(request)
-        .await
-        .map_err(|e| TranscriptionError::ApiError(e.to_string()))?;
-
-    let content = response
-        .choices
-        .first()
-        .and_then(|choice| choice.message.content.clone())
-        .ok_or_else(|| TranscriptionError::TranscriptionFailed("No content in response".to_string()))?;
-
-    parse_json_array::<Vec<String>>(&content)
 }
 
-async fn get_transcript_insights_inner(app: AppHandle, text: String) -> Result<TranscriptInsights, TranscriptionError> {
+pub(crate) async fn get_transcript_insights_inner(app: AppHandle, text: String, path: Option<&Path>) -> Result<TranscriptInsights, TranscriptionError> {
     // Validate input
     if text.trim().is_empty() {
         return Err(TranscriptionError::TranscriptionFailed(
@@ -517,13 +480,9 @@ async fn get_transcript_insights_inner(app: AppHandle, text: String) -> Result<T
         ));
     }
 
-    // Normalize text for consistent hashing (trim, collapse whitespace)
-    let normalized_text = text.trim();
-    // Compute hash of normalized transcript text for caching
-    let hash = format!("{:x}", md5::compute(normalized_text));
-    
-    // Read existing cache (if any)
-    let existing = read_cache(&app, &hash).await?;
+    let hash = storage::transcript_hash(&text);
+    let managed_dir = insights_dir(&app, path);
+    let existing = load_insights(&app, &hash, managed_dir.as_deref());
     
     // Determine which fields need generation
     let needs_summary = existing.as_ref().and_then(|c| c.summary.as_ref()).is_none();
@@ -539,62 +498,25 @@ async fn get_transcript_insights_inner(app: AppHandle, text: String) -> Result<T
     info!("Generating missing insights for hash: {} (summary: {}, actions: {}, topics: {})", 
           hash, needs_summary, needs_actions, needs_topics);
     
-    // Generate missing fields in parallel
-    let (summary_result, actions_result, topics_result) = tokio::try_join!(
-        async {
-            if needs_summary {
-                summarize_transcript_api(text.clone()).await
-            } else {
-                // Use existing summary
-                Ok::<String, TranscriptionError>(existing.as_ref().unwrap().summary.clone().unwrap())
-            }
-        },
-        async {
-            if needs_actions {
-                recommend_actions_inner(text.clone()).await
-            } else {
-                // Use existing actions
-                Ok::<Vec<RecommendedAction>, TranscriptionError>(existing.as_ref().unwrap().actions.clone().unwrap())
-            }
-        },
-        async {
-            if needs_topics {
-                extract_key_topics_inner(text.clone()).await
-            } else {
-                // Use existing topics
-                Ok::<Vec<String>, TranscriptionError>(existing.as_ref().unwrap().topics.clone().unwrap())
-            }
-        },
-    )?;
-    
-    // Build insights (merge with existing)
+    // One structured request covers all three fields; cached ones are kept
+    let engine = insights::engine(&app, path)?;
+    let generated = engine.generate_all(&text).await?;
+    let existing = existing.unwrap_or(TranscriptInsights {
+        summary: None,
+        actions: None,
+        topics: None,
+    });
     let insights = TranscriptInsights {
-        summary: Some(summary_result),
-        actions: Some(actions_result),
-        topics: Some(topics_result),
+        summary: existing.summary.or(Some(generated.summary)),
+        actions: existing.actions.or(Some(generated.actions)),
+        topics: existing.topics.or(Some(generated.topics)),
     };
     
-    // Write to cache
-    let _ = write_cache(&app, &hash, &insights).await;
+    store_insights(&app, &hash, managed_dir.as_deref(), &insights, Some(engine.model()));
     
     Ok(insights)
 }
 
-fn parse_json_array<T: DeserializeOwned>(content: &str) -> Result<T, TranscriptionError> {
-    let trimmed = content.trim();
-    if let Ok(parsed) = serde_json::from_str::<T>(trimmed) {
-        return Ok(parsed);
-    }
-
-    if let (Some(start), Some(end)) = (trimmed.find('['), trimmed.rfind(']')) {
-        let slice = &trimmed[start..=end];
-        return serde_json::from_str::<T>(slice)
-            .map_err(|e| TranscriptionError::TranscriptionFailed(format!("Invalid JSON: {}", e)));
-    }
-
-    Err(TranscriptionError::TranscriptionFailed("Invalid JSON: no array found".to_string()))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,140 @@
+use log::{info, warn};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::audio::peaks::{self, WaveformPeaks};
+use crate::storage;
+
+/// Event emitted while peaks of a file are computed, payload is a
+/// `WaveformProgress`.
+pub const WAVEFORM_EVENT: &str = "waveform-progress";
+
+// Progress events are sent at most this often per file
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformProgress {
+    pub path: String,
+    /// Decoded fraction in [0.0, 1.0].
+    pub progress: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformData {
+    pub sample_rate: u32,
+    pub duration: f64,
+    /// Source frames each peak covers; may be fractional when peaks were
+    /// merged to fit `max_peaks`.
+    pub samples_per_peak: f64,
+    /// Interleaved `min, max` per peak, scaled to i16.
+    pub peaks: Vec<i16>,
+}
+
+/// Min/max peaks of `path` with at most `max_peaks` peaks, picked from the
+/// finest cached resolution that fits. Peaks are computed on first use and
+/// cached as `peaks.bin` in the managed directory; `waveform-progress`
+/// events report decoding progress meanwhile.
+#[tauri::command]
+pub async fn get_waveform_peaks(app: AppHandle, path: String, max_peaks: usize) -> Result<WaveformData, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let source = PathBuf::from(&path);
+        let peaks = load_peaks(&app, &source)?;
+        Ok(waveform_data(&peaks, max_peaks.max(1)))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn load_peaks(app: &AppHandle, source: &PathBuf) -> Result<WaveformPeaks, String> {
+    let managed_dir = storage::ensure_managed_dir(app, source)?;
+    if let Some(cached) = WaveformPeaks::read(&managed_dir) {
+        return Ok(cached);
+    }
+
+    let audio = storage::audio_source(&managed_dir, source)?;
+    let path = source.to_string_lossy().to_string();
+    let mut last_emit: Option<Instant> = None;
+    let computed = peaks::compute_peaks(&audio, |progress| {
+        let due = !last_emit.is_some_and(|at| at.elapsed() < PROGRESS_INTERVAL);
+        if due || progress >= 1.0 {
+            last_emit = Some(Instant::now());
+            let event = WaveformProgress {
+                path: path.clone(),
+                progress,
+            };
+            if let Err(e) = app.emit(WAVEFORM_EVENT, &event) {
+                warn!("Failed to emit waveform progress: {}", e);
+            }
+        }
+    })?;
+    info!("Computed waveform peaks for {:?}", source);
+
+    if let Err(e) = computed.write(&managed_dir) {
+        warn!("Failed to cache peaks in {:?}: {}", managed_dir, e);
+    }
+    storage::touch_manifest(&managed_dir);
+    Ok(computed)
+}
+
+fn waveform_data(peaks: &WaveformPeaks, max_peaks: usize) -> WaveformData {
+    let Some(level) = peaks.level_for(max_peaks) else {
+        return WaveformData {
+            sample_rate: peaks.sample_rate,
+            duration: peaks.duration_seconds(),
+            samples_per_peak: 0.0,
+            peaks: Vec::new(),
+        };
+    };
+
+    let (samples_per_peak, values) = if level.len() <= max_peaks {
+        (level.samples_per_peak as f64, level.peaks.clone())
+    } else {
+        // Even the coarsest level is too fine, merge evenly spaced groups
+        let len = level.len();
+        let mut values = Vec::with_capacity(max_peaks * 2);
+        for i in 0..max_peaks {
+            let start = i * len / max_peaks;
+            let end = ((i + 1) * len / max_peaks).max(start + 1);
+            let group = &level.peaks[start * 2..end * 2];
+            values.push(group.iter().step_by(2).copied().min().unwrap_or(0));
+            values.push(group.iter().skip(1).step_by(2).copied().max().unwrap_or(0));
+        }
+        (level.samples_per_peak as f64 * len as f64 / max_peaks as f64, values)
+    };
+
+    WaveformData {
+        sample_rate: peaks.sample_rate,
+        duration: peaks.duration_seconds(),
+        samples_per_peak,
+        peaks: values,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::peaks::PeakLevel;
+
+    #[test]
+    fn merges_the_coarsest_level_down_to_max_peaks() {
+        let peaks = WaveformPeaks {
+            sample_rate: 8_000,
+            frames: 16_000,
+            levels: vec![PeakLevel {
+                samples_per_peak: 4_000,
+                peaks: vec![-1, 1, -4, 2, -2, 8, -3, 3],
+            }],
+        };
+
+        let data = waveform_data(&peaks, 2);
+
+        assert_eq!(data.peaks, vec![-4, 2, -3, 8]);
+        assert_eq!(data.samples_per_peak, 8_000.0);
+        assert_eq!(data.duration, 2.0);
+        assert_eq!(waveform_data(&peaks, 10).peaks.len(), 8);
+    }
+}
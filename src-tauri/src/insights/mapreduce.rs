@@ -0,0 +1,311 @@
+use log::{info, warn};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use super::GeneratedInsights;
+
+/// Rough token count. Uses 3 characters per token rather than the usual 4 so
+/// chunks stay inside the context window for non-English text too.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(3)
+}
+
+/// Splits `text` into chunks of at most `max_tokens`, cutting at sentence
+/// ends. Sentences that don't fit on their own are cut at whitespace.
+///
+/// Where to cut is decided by the sentences themselves rather than by filling
+/// each chunk up, so an edit only moves the boundaries next to it and the
+/// chunks after it keep their cache keys.
+pub fn split_text(text: &str, max_tokens: usize) -> Vec<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    if estimate_tokens(text) <= max_tokens {
+        return vec![text.to_string()];
+    }
+
+    // Chunks end up around half the budget, which leaves room to grow into
+    // before an edit forces a cut of its own
+    let min_tokens = max_tokens / 4;
+    let target = (max_tokens / 3).max(1);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for sentence in split_sentences(text) {
+        if estimate_tokens(&sentence) > max_tokens {
+            flush(&mut chunks, &mut current);
+            chunks.extend(split_words(&sentence, max_tokens));
+            continue;
+        }
+        if !current.is_empty() && estimate_tokens(&current) + 1 + estimate_tokens(&sentence) > max_tokens {
+            flush(&mut chunks, &mut current);
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&sentence);
+        if estimate_tokens(&current) >= min_tokens && is_cut_point(&sentence, target) {
+            flush(&mut chunks, &mut current);
+        }
+    }
+    flush(&mut chunks, &mut current);
+    chunks
+}
+
+/// Whether a chunk ends after `sentence`. Depends on nothing but the sentence,
+/// and is hit about once per `target` tokens.
+fn is_cut_point(sentence: &str, target: usize) -> bool {
+    let digest = md5::compute(sentence);
+    let value = u64::from_le_bytes(digest.0[..8].try_into().unwrap());
+    value % (target as u64) < estimate_tokens(sentence) as u64
+}
+
+fn flush(chunks: &mut Vec<String>, current: &mut String) {
+    if !current.is_empty() {
+        chunks.push(std::mem::take(current));
+    }
+}
+
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        current.push(c);
+        let ends_sentence = c == '\n'
+            || (matches!(c, '.' | '?' | '!') && chars.peek().map_or(true, |next| next.is_whitespace()));
+        if ends_sentence {
+            let sentence = current.trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+            current.clear();
+        }
+    }
+    let sentence = current.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence.to_string());
+    }
+    sentences
+}
+
+fn split_words(sentence: &str, max_tokens: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for word in sentence.split_whitespace() {
+        if !current.is_empty() && estimate_tokens(&current) + 1 + estimate_tokens(word) > max_tokens {
+            flush(&mut pieces, &mut current);
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    flush(&mut pieces, &mut current);
+    pieces
+}
+
+/// Renders chunk results as the notes fed to the reduce prompt.
+pub fn format_partials(partials: &[GeneratedInsights]) -> String {
+    partials
+        .iter()
+        .enumerate()
+        .map(|(i, partial)| format_part(i, partial))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// `format_partials` cut down to `max_tokens`: when the notes don't fit,
+/// each part keeps an equal share of the budget and loses its tail.
+pub fn format_partials_within(partials: &[GeneratedInsights], max_tokens: usize) -> String {
+    let notes = format_partials(partials);
+    if partials.is_empty() || estimate_tokens(&notes) <= max_tokens {
+        return notes;
+    }
+    // One token of each share goes to the separator
+    let share_chars = (max_tokens / partials.len()).saturating_sub(1).max(1) * 3;
+    partials
+        .iter()
+        .enumerate()
+        .map(|(i, partial)| format_part(i, partial).chars().take(share_chars).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn format_part(index: usize, partial: &GeneratedInsights) -> String {
+    let actions: Vec<String> = partial
+        .actions
+        .iter()
+        .map(|a| format!("- {}: {}", a.title, a.description))
+        .collect();
+    format!(
+        "Part {}\nSummary:\n{}\nActions:\n{}\nTopics: {}",
+        index + 1,
+        partial.summary.trim(),
+        actions.join("\n"),
+        partial.topics.join(", ")
+    )
+}
+
+/// Groups consecutive partials so each group's notes fit in `max_tokens`.
+/// Every group takes at least two partials so each reduce round shrinks the
+/// list; render groups with `format_partials_within` so large partials are
+/// cut to fit.
+pub fn group_partials(partials: &[GeneratedInsights], max_tokens: usize) -> Vec<std::ops::Range<usize>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    while start < partials.len() {
+        let mut end = (start + 2).min(partials.len());
+        while end < partials.len() && estimate_tokens(&format_partials(&partials[start..=end])) <= max_tokens {
+            end += 1;
+        }
+        groups.push(start..end);
+        start = end;
+    }
+    groups
+}
+
+/// Cache key for one chunk: the model and prompt are part of it so changing
+/// either recomputes the chunk.
+pub fn chunk_key(model: &str, template: &str, chunk: &str) -> String {
+    format!("{:x}", md5::compute(format!("{}\n{}\n{}", model, template, chunk)))
+}
+
+/// Chunk results not used for this long are removed by `prune_cache`.
+pub const CHUNK_CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+pub fn read_cached(dir: &Path, key: &str) -> Option<GeneratedInsights> {
+    let path = dir.join(format!("{}.json", key));
+    let content = fs::read_to_string(&path).ok()?;
+    let insights = serde_json::from_str(&content).ok()?;
+    // The mtime marks last use for `prune_cache`
+    let touched = fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(e) = touched {
+        warn!("Failed to touch chunk cache {}: {}", key, e);
+    }
+    Some(insights)
+}
+
+/// Removes chunk results in `dir` last used more than `max_age` ago.
+pub fn prune_cache(dir: &Path, max_age: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut removed = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let unused = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if unused && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    if removed > 0 {
+        info!("Removed {} unused chunk results from {:?}", removed, dir);
+    }
+}
+
+pub fn write_cached(dir: &Path, key: &str, insights: &GeneratedInsights) {
+    let result = fs::create_dir_all(dir)
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_string_pretty(insights).map_err(|e| e.to_string()))
+        .and_then(|json| fs::write(dir.join(format!("{}.json", key)), json).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+        warn!("Failed to write chunk cache {}: {}", key, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    #[test]
+    fn splits_at_sentence_ends_within_budget() {
+        let text = "First sentence here. Second one follows? Third one ends it! Fourth.";
+        let chunks = split_text(text, 15);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(estimate_tokens(chunk) <= 15, "chunk too long: {}", chunk);
+            assert!(chunk.ends_with(['.', '?', '!']), "cut mid-sentence: {}", chunk);
+        }
+        assert_eq!(chunks.join(" "), text);
+    }
+
+    #[test]
+    fn cuts_overlong_sentences_at_whitespace() {
+        let text = "word ".repeat(100);
+        let chunks = split_text(&text, 10);
+
+        assert!(chunks.iter().all(|c| estimate_tokens(c) <= 10));
+        assert_eq!(chunks.join(" "), text.trim());
+    }
+
+    #[test]
+    fn edits_keep_later_chunks() {
+        let sentences: Vec<String> = (0..200)
+            .map(|i| format!("Sentence number {} talks about item {}.", i, i * 7))
+            .collect();
+        let text = sentences.join(" ");
+        let edited = text.replacen("Sentence number 3 ", "Sentence number three, edited at length, ", 1);
+
+        let before = split_text(&text, 100);
+        let after = split_text(&edited, 100);
+
+        assert!(before.len() > 4);
+        assert_ne!(before, after);
+        let keys = |chunks: &[String]| -> Vec<String> {
+            chunks.iter().map(|chunk| chunk_key("model", "template", chunk)).collect()
+        };
+        assert_eq!(keys(&before[2..]), keys(&after[after.len() - (before.len() - 2)..]));
+        assert!(after.iter().all(|chunk| estimate_tokens(chunk) <= 100));
+    }
+
+    #[test]
+    fn groups_always_shrink() {
+        let big = GeneratedInsights {
+            summary: "x".repeat(300),
+            actions: Vec::new(),
+            topics: Vec::new(),
+        };
+        let partials = vec![big; 5];
+
+        let groups = group_partials(&partials, 10);
+
+        assert_eq!(groups, vec![0..2, 2..4, 4..5]);
+        for group in groups {
+            assert!(estimate_tokens(&format_partials_within(&partials[group], 40)) <= 40);
+        }
+        assert_eq!(format_partials_within(&partials[..1], 1_000), format_partials(&partials[..1]));
+    }
+
+    #[test]
+    fn prunes_only_unused_chunk_results() {
+        let dir = scratch_dir("mapreduce-prune");
+        let insights = GeneratedInsights {
+            summary: "s".to_string(),
+            actions: Vec::new(),
+            topics: Vec::new(),
+        };
+        write_cached(&dir, "old", &insights);
+        write_cached(&dir, "new", &insights);
+        let old = fs::File::options().write(true).open(dir.join("old.json")).unwrap();
+        old.set_modified(SystemTime::now() - CHUNK_CACHE_MAX_AGE * 2).unwrap();
+
+        prune_cache(&dir, CHUNK_CACHE_MAX_AGE);
+
+        assert!(read_cached(&dir, "old").is_none());
+        assert!(read_cached(&dir, "new").is_some());
+    }
+}
@@ -0,0 +1,351 @@
+use futures::future::BoxFuture;
+use futures::{StreamExt, TryStreamExt};
+use log::info;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::transcription::{RecommendedAction, TranscriptionError};
+use crate::settings::{self, AppSettings, ChatProviderKind};
+use crate::storage;
+
+mod mapreduce;
+mod ollama;
+mod openai;
+mod prompts;
+
+pub use ollama::OllamaChat;
+pub use openai::OpenAiChat;
+pub use prompts::PromptTemplates;
+
+/// JSON schema the model must follow (structured outputs).
+pub struct ResponseSchema {
+    pub name: &'static str,
+    pub schema: Value,
+}
+
+/// A chat model that turns a prompt into a text completion.
+pub trait ChatBackend: Send + Sync {
+    /// Short identifier used in logs, e.g. "openai" or "ollama".
+    fn name(&self) -> &'static str;
+
+    /// Model identifier; part of the chunk cache key.
+    fn model(&self) -> &str;
+
+    fn complete<'a>(&'a self, prompt: &'a str, schema: Option<&'a ResponseSchema>) -> BoxFuture<'a, Result<String, TranscriptionError>>;
+}
+
+// Tokens kept free for the prompt template and the response
+const PROMPT_RESERVE_TOKENS: usize = 2_000;
+const MIN_CHUNK_TOKENS: usize = 500;
+// Chunk requests in flight at once during the map step
+const MAP_CONCURRENCY: usize = 3;
+
+/// Summary, actions and topics produced by a single combined request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedInsights {
+    pub summary: String,
+    pub actions: Vec<RecommendedAction>,
+    pub topics: Vec<String>,
+}
+
+/// Owns the chat backend and prompt templates used for transcript insights.
+///
+/// Transcripts longer than `chunk_tokens` are summarized map-reduce style:
+/// each chunk is analyzed on its own (results cached by chunk content), then
+/// the chunk results are merged by the reduce prompt.
+pub struct InsightsEngine {
+    backend: Box<dyn ChatBackend>,
+    prompts: PromptTemplates,
+    chunk_tokens: usize,
+    chunk_cache: Option<PathBuf>,
+}
+
+impl InsightsEngine {
+    pub fn new(backend: Box<dyn ChatBackend>, prompts: PromptTemplates, context_tokens: usize) -> Self {
+        Self {
+            backend,
+            prompts,
+            chunk_tokens: context_tokens.saturating_sub(PROMPT_RESERVE_TOKENS).max(MIN_CHUNK_TOKENS),
+            chunk_cache: None,
+        }
+    }
+
+    pub fn from_settings(settings: &AppSettings, kind: ChatProviderKind) -> Result<Self, TranscriptionError> {
+        let (backend, context_tokens): (Box<dyn ChatBackend>, usize) = match kind {
+            ChatProviderKind::OpenAi => (Box::new(OpenAiChat::from_settings(settings)?), settings.chat_context_tokens),
+            ChatProviderKind::Ollama => (Box::new(OllamaChat::from_settings(settings)?), settings.ollama_context_tokens),
+        };
+        Ok(Self::new(backend, PromptTemplates::default(), context_tokens))
+    }
+
+    /// Stores per-chunk results in `dir` so re-runs only recompute chunks
+    /// whose text changed.
+    pub fn with_chunk_cache(mut self, dir: PathBuf) -> Self {
+        self.chunk_cache = Some(dir);
+        self
+    }
+
+    /// Model of the chat backend, recorded with stored insights.
+    pub fn model(&self) -> &str {
+        self.backend.model()
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        mapreduce::estimate_tokens(text) <= self.chunk_tokens
+    }
+
+    pub async fn summary(&self, text: &str) -> Result<String, TranscriptionError> {
+        let text = non_empty(text)?;
+        if !self.fits(text) {
+            return Ok(self.generate_all(text).await?.summary);
+        }
+        self.backend
+            .complete(&prompts::render(&self.prompts.summary, text), None)
+            .await
+    }
+
+    pub async fn actions(&self, text: &str) -> Result<Vec<RecommendedAction>, TranscriptionError> {
+        let text = non_empty(text)?;
+        if !self.fits(text) {
+            return Ok(self.generate_all(text).await?.actions);
+        }
+        let content = self
+            .backend
+            .complete(&prompts::render(&self.prompts.actions, text), None)
+            .await?;
+        parse_json_block(&content, '[', ']')
+    }
+
+    pub async fn topics(&self, text: &str) -> Result<Vec<String>, TranscriptionError> {
+        let text = non_empty(text)?;
+        if !self.fits(text) {
+            return Ok(self.generate_all(text).await?.topics);
+        }
+        let content = self
+            .backend
+            .complete(&prompts::render(&self.prompts.topics, text), None)
+            .await?;
+        parse_json_block(&content, '[', ']')
+    }
+
+    /// Generates summary, actions and topics with structured-output requests:
+    /// one for a transcript that fits the context window, otherwise one per
+    /// chunk plus the reduce requests that merge them.
+    pub async fn generate_all(&self, text: &str) -> Result<GeneratedInsights, TranscriptionError> {
+        let text = non_empty(text)?;
+        let chunks = mapreduce::split_text(text, self.chunk_tokens);
+        if chunks.len() > 1 {
+            info!("Transcript split into {} chunks for {}", chunks.len(), self.backend.name());
+        }
+
+        let mut partials: Vec<GeneratedInsights> = futures::stream::iter(chunks.iter())
+            .map(|chunk| self.map_chunk(chunk))
+            .buffered(MAP_CONCURRENCY)
+            .try_collect()
+            .await?;
+        if let Some(dir) = self.chunk_cache.as_deref().filter(|_| chunks.len() > 1) {
+            mapreduce::prune_cache(dir, mapreduce::CHUNK_CACHE_MAX_AGE);
+        }
+
+        while partials.len() > 1 {
+            let mut merged = Vec::new();
+            for group in mapreduce::group_partials(&partials, self.chunk_tokens) {
+                if group.len() == 1 {
+                    merged.push(partials[group.start].clone());
+                    continue;
+                }
+                let notes = mapreduce::format_partials_within(&partials[group], self.chunk_tokens);
+                merged.push(self.structured(&self.prompts.reduce, &notes).await?);
+            }
+            partials = merged;
+        }
+
+        partials
+            .pop()
+            .ok_or_else(|| TranscriptionError::TranscriptionFailed("Transcript text is empty".to_string()))
+    }
+
+    async fn map_chunk(&self, chunk: &str) -> Result<GeneratedInsights, TranscriptionError> {
+        let key = mapreduce::chunk_key(self.backend.model(), &self.prompts.combined, chunk);
+        if let Some(cached) = self.chunk_cache.as_deref().and_then(|dir| mapreduce::read_cached(dir, &key)) {
+            return Ok(cached);
+        }
+
+        let insights = self.structured(&self.prompts.combined, chunk).await?;
+        if let Some(dir) = &self.chunk_cache {
+            mapreduce::write_cached(dir, &key, &insights);
+        }
+        Ok(insights)
+    }
+
+    async fn structured(&self, template: &str, text: &str) -> Result<GeneratedInsights, TranscriptionError> {
+        let schema = ResponseSchema {
+            name: "transcript_insights",
+            schema: prompts::combined_schema(),
+        };
+        let content = self
+            .backend
+            .complete(&prompts::render(template, text), Some(&schema))
+            .await?;
+        parse_json_block(&content, '{', '}')
+    }
+}
+
+fn non_empty(text: &str) -> Result<&str, TranscriptionError> {
+    if text.trim().is_empty() {
+        return Err(TranscriptionError::TranscriptionFailed(
+            "Transcript text is empty".to_string(),
+        ));
+    }
+    Ok(text)
+}
+
+/// Parses `content` as JSON, falling back to the outermost `open`..`close`
+/// block for models that wrap the JSON in prose or code fences.
+pub fn parse_json_block<T: DeserializeOwned>(content: &str, open: char, close: char) -> Result<T, TranscriptionError> {
+    let trimmed = content.trim();
+    if let Ok(parsed) = serde_json::from_str::<T>(trimmed) {
+        return Ok(parsed);
+    }
+
+    if let (Some(start), Some(end)) = (trimmed.find(open), trimmed.rfind(close)) {
+        if start < end {
+            let slice = &trimmed[start..=end];
+            return serde_json::from_str::<T>(slice)
+                .map_err(|e| TranscriptionError::TranscriptionFailed(format!("Invalid JSON: {}", e)));
+        }
+    }
+
+    Err(TranscriptionError::TranscriptionFailed(format!("Invalid JSON: no {} found", open)))
+}
+
+/// Lazily built engines, one per chat provider, shared by all insight
+/// commands; registered as Tauri state and rebuilt after settings change.
+#[derive(Default)]
+pub struct EngineCache(Mutex<HashMap<ChatProviderKind, Arc<InsightsEngine>>>);
+
+impl EngineCache {
+    pub fn invalidate(&self) {
+        if let Ok(mut engines) = self.0.lock() {
+            engines.clear();
+        }
+    }
+}
+
+/// Engine for the chat provider configured for the library containing
+/// `path` (the global provider when `path` is `None`).
+pub fn engine(app: &AppHandle, path: Option<&Path>) -> Result<Arc<InsightsEngine>, TranscriptionError> {
+    let settings = settings::load_settings(app).map_err(TranscriptionError::SettingsError)?;
+    let kind = settings.chat_provider_for(path);
+
+    let cache = app.state::<EngineCache>();
+    let mut engines = cache
+        .0
+        .lock()
+        .map_err(|e| TranscriptionError::TranscriptionFailed(e.to_string()))?;
+
+    if let Some(engine) = engines.get(&kind) {
+        return Ok(engine.clone());
+    }
+
+    let mut engine = InsightsEngine::from_settings(&settings, kind)?;
+    if let Ok(dir) = storage::get_insights_cache_dir(app) {
+        engine = engine.with_chunk_cache(dir.join("chunks"));
+    }
+    let engine = Arc::new(engine);
+    engines.insert(kind, engine.clone());
+    Ok(engine)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CannedBackend(&'static str);
+
+    impl ChatBackend for CannedBackend {
+        fn name(&self) -> &'static str {
+            "canned"
+        }
+
+        fn model(&self) -> &str {
+            "canned"
+        }
+
+        fn complete<'a>(&'a self, _prompt: &'a str, _schema: Option<&'a ResponseSchema>) -> BoxFuture<'a, Result<String, TranscriptionError>> {
+            Box::pin(async move { Ok(self.0.to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_all_parses_wrapped_json() {
+        let backend = CannedBackend(
+            "```json\n{\"summary\": \"- point\", \"actions\": [{\"title\": \"Ship it\", \"description\": \"Release on Friday\"}], \"topics\": [\"release\"]}\n```",
+        );
+        let engine = InsightsEngine::new(Box::new(backend), PromptTemplates::default(), 16_000);
+
+        let insights = engine.generate_all("we ship on friday").await.unwrap();
+
+        assert_eq!(insights.summary, "- point");
+        assert_eq!(insights.actions[0].title, "Ship it");
+        assert_eq!(insights.topics, vec!["release".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn rejects_empty_transcript() {
+        let engine = InsightsEngine::new(Box::new(CannedBackend("[]")), PromptTemplates::default(), 16_000);
+
+        assert!(engine.topics("   ").await.is_err());
+    }
+
+    // Answers chunk prompts with the chunk's first word as topic and reduce
+    // prompts with a fixed result
+    struct EchoBackend;
+
+    impl ChatBackend for EchoBackend {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn model(&self) -> &str {
+            "echo"
+        }
+
+        fn complete<'a>(&'a self, prompt: &'a str, _schema: Option<&'a ResponseSchema>) -> BoxFuture<'a, Result<String, TranscriptionError>> {
+            Box::pin(async move {
+                if prompt.contains("Notes:") {
+                    return Ok(r#"{"summary": "merged", "actions": [], "topics": ["all"]}"#.to_string());
+                }
+                let chunk = prompt.rsplit("Transcript:\n\n").next().unwrap_or("");
+                let first = chunk.split_whitespace().next().unwrap_or("");
+                Ok(format!(r#"{{"summary": "part", "actions": [], "topics": ["{}"]}}"#, first))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn long_transcripts_are_mapped_then_reduced() {
+        let engine = InsightsEngine::new(Box::new(EchoBackend), PromptTemplates::default(), 0);
+        let text = "Alpha sentence. ".repeat(2_000);
+
+        let insights = engine.generate_all(&text).await.unwrap();
+
+        assert_eq!(insights.summary, "merged");
+        assert_eq!(insights.topics, vec!["all".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn short_transcripts_skip_the_reduce_step() {
+        let engine = InsightsEngine::new(Box::new(EchoBackend), PromptTemplates::default(), 16_000);
+
+        let insights = engine.generate_all("Alpha beta. Gamma.").await.unwrap();
+
+        assert_eq!(insights.summary, "part");
+        assert_eq!(insights.topics, vec!["Alpha".to_string()]);
+    }
+}
@@ -0,0 +1,159 @@
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt;
+use std::time::Duration;
+use tauri_plugin_http::reqwest::{self, header, Client};
+
+use super::{ChatBackend, ResponseSchema};
+use crate::commands::transcription::TranscriptionError;
+use crate::retry::{self, ErrorClass, RetryPolicy, RetryableError};
+use crate::settings::AppSettings;
+
+// Local models on CPU can take minutes for one long chunk
+const REQUEST_TIMEOUT_SECONDS: u64 = 600;
+
+/// Chat completions against a local Ollama server through its native
+/// `/api/chat` endpoint, which (unlike the OpenAI-compatible one) lets us set
+/// the context window and a JSON schema for the output.
+pub struct OllamaChat {
+    client: Client,
+    base_url: String,
+    model: String,
+    context_tokens: usize,
+    retry: RetryPolicy,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+enum OllamaError {
+    Http(reqwest::Error),
+    Status {
+        status: u16,
+        retry_after: Option<Duration>,
+        body: String,
+    },
+}
+
+impl fmt::Display for OllamaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OllamaError::Http(e) => write!(f, "{}", e),
+            OllamaError::Status { status, body, .. } => write!(f, "HTTP {}: {}", status, body),
+        }
+    }
+}
+
+impl RetryableError for OllamaError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            OllamaError::Http(e) if e.is_timeout() || e.is_connect() || e.is_request() => ErrorClass::Transient,
+            OllamaError::Http(_) => ErrorClass::Permanent,
+            OllamaError::Status { status, retry_after, .. } => retry::classify_status(*status, *retry_after),
+        }
+    }
+
+    fn into_error(self) -> TranscriptionError {
+        match &self {
+            OllamaError::Http(e) if e.is_timeout() => TranscriptionError::Timeout(self.to_string()),
+            OllamaError::Http(e) if e.is_connect() => {
+                TranscriptionError::NetworkError(format!("Could not reach Ollama ({}); is it running?", self))
+            }
+            OllamaError::Http(_) => TranscriptionError::NetworkError(self.to_string()),
+            // Ollama answers 404 for models that haven't been pulled
+            OllamaError::Status { status: 404, .. } => TranscriptionError::ModelNotFound(self.to_string()),
+            OllamaError::Status { .. } => TranscriptionError::ApiError(self.to_string()),
+        }
+    }
+}
+
+impl OllamaChat {
+    pub fn from_settings(settings: &AppSettings) -> Result<Self, TranscriptionError> {
+        let base_url = settings.ollama_base_url.trim().trim_end_matches('/');
+        if base_url.is_empty() {
+            return Err(TranscriptionError::SettingsError("Ollama base URL is not set".to_string()));
+        }
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+            .build()?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.to_string(),
+            model: settings.ollama_model.clone(),
+            context_tokens: settings.ollama_context_tokens,
+            retry: RetryPolicy::from_settings(settings),
+        })
+    }
+
+    async fn complete_inner(&self, prompt: &str, schema: Option<&ResponseSchema>) -> Result<String, TranscriptionError> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": [{ "role": "user", "content": prompt }],
+            "stream": false,
+            "options": { "num_ctx": self.context_tokens },
+        });
+        if let Some(schema) = schema {
+            body["format"] = schema.schema.clone();
+        }
+        let body = body.to_string();
+        let url = format!("{}/api/chat", self.base_url);
+
+        let content = retry::with_retry(&self.retry, "Ollama chat request", || self.post(&url, body.clone())).await?;
+
+        serde_json::from_str::<ChatResponse>(&content)
+            .map(|response| response.message.content)
+            .map_err(|e| TranscriptionError::ApiError(format!("Unexpected Ollama response: {}", e)))
+    }
+
+    async fn post(&self, url: &str, body: String) -> Result<String, OllamaError> {
+        let response = self
+            .client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(OllamaError::Http)?;
+
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(retry::parse_retry_after_header);
+        let text = response.text().await.map_err(OllamaError::Http)?;
+
+        if status >= 400 {
+            return Err(OllamaError::Status {
+                status,
+                retry_after,
+                body: text,
+            });
+        }
+        Ok(text)
+    }
+}
+
+impl ChatBackend for OllamaChat {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(&'a self, prompt: &'a str, schema: Option<&'a ResponseSchema>) -> BoxFuture<'a, Result<String, TranscriptionError>> {
+        Box::pin(self.complete_inner(prompt, schema))
+    }
+}
@@ -0,0 +1,146 @@
+use async_openai::{
+    config::OpenAIConfig,
+    types::chat::{
+        ChatCompletionRequestMessage,
+        ChatCompletionRequestUserMessage,
+        ChatCompletionRequestUserMessageContent,
+        CreateChatCompletionRequestArgs,
+        ResponseFormat,
+        ResponseFormatJsonSchema,
+    },
+    Client,
+};
+use futures::future::BoxFuture;
+use log::warn;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use super::{ChatBackend, ResponseSchema};
+use crate::commands::transcription::TranscriptionError;
+use crate::retry::{self, RetryPolicy};
+use crate::settings::AppSettings;
+
+// How a schema is requested, strictest first. Models without structured
+// outputs (gpt-3.5-turbo, many self-hosted servers) reject `json_schema`;
+// the prompts spell out the fields, so a plain JSON reply still parses.
+const JSON_SCHEMA: u8 = 0;
+const JSON_OBJECT: u8 = 1;
+const PLAIN_TEXT: u8 = 2;
+
+/// Chat completions against OpenAI or any OpenAI-compatible server.
+pub struct OpenAiChat {
+    client: Client<OpenAIConfig>,
+    model: String,
+    retry: RetryPolicy,
+    // Strictest format the model accepted so far
+    json_mode: AtomicU8,
+}
+
+impl OpenAiChat {
+    pub fn from_settings(settings: &AppSettings) -> Result<Self, TranscriptionError> {
+        let config = settings.openai_config().ok_or(TranscriptionError::MissingApiKey)?;
+
+        Ok(Self {
+            client: retry::openai_client(config),
+            model: settings.chat_model.clone(),
+            retry: RetryPolicy::from_settings(settings),
+            json_mode: AtomicU8::new(JSON_SCHEMA),
+        })
+    }
+
+    async fn complete_with_fallback(&self, prompt: &str, schema: Option<&ResponseSchema>) -> Result<String, TranscriptionError> {
+        loop {
+            let mode = self.json_mode.load(Ordering::Relaxed);
+            match self.complete_inner(prompt, schema.filter(|_| mode < PLAIN_TEXT), mode).await {
+                Err(e) if schema.is_some() && mode < PLAIN_TEXT && rejects_response_format(&e) => {
+                    warn!("{} rejected the response format, falling back: {}", self.model, e);
+                    // compare_exchange so concurrent chunk requests step down only once
+                    let _ = self
+                        .json_mode
+                        .compare_exchange(mode, mode + 1, Ordering::Relaxed, Ordering::Relaxed);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn complete_inner(&self, prompt: &str, schema: Option<&ResponseSchema>, mode: u8) -> Result<String, TranscriptionError> {
+        let mut args = CreateChatCompletionRequestArgs::default();
+        args.model(&self.model)
+            .messages(vec![ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+                content: ChatCompletionRequestUserMessageContent::Text(prompt.to_string()),
+                name: None,
+            })]);
+        match schema {
+            Some(schema) if mode == JSON_SCHEMA => {
+                args.response_format(ResponseFormat::JsonSchema {
+                    json_schema: ResponseFormatJsonSchema {
+                        description: None,
+                        name: schema.name.to_string(),
+                        schema: Some(schema.schema.clone()),
+                        strict: Some(true),
+                    },
+                });
+            }
+            Some(_) if mode == JSON_OBJECT => {
+                args.response_format(ResponseFormat::JsonObject);
+            }
+            _ => {}
+        }
+        let request = args
+            .build()
+            .map_err(|e| TranscriptionError::RequestError(e.to_string()))?;
+
+        let client = &self.client;
+        let response = retry::with_retry(&self.retry, "Chat request", || {
+            let request = request.clone();
+            async move { client.chat().create(request).await }
+        })
+        .await?;
+
+        response
+            .choices
+            .first()
+            .and_then(|choice| choice.message.content.clone())
+            .ok_or_else(|| TranscriptionError::TranscriptionFailed("No content in response".to_string()))
+    }
+}
+
+impl ChatBackend for OpenAiChat {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(&'a self, prompt: &'a str, schema: Option<&'a ResponseSchema>) -> BoxFuture<'a, Result<String, TranscriptionError>> {
+        Box::pin(self.complete_with_fallback(prompt, schema))
+    }
+}
+
+// A 400 about `response_format`, e.g. "Invalid parameter: 'response_format' of
+// type 'json_schema' is not supported with this model."
+fn rejects_response_format(err: &TranscriptionError) -> bool {
+    match err {
+        TranscriptionError::ApiError(message) => {
+            message.contains("response_format") || message.contains("json_schema") || message.contains("json_object")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_response_format_rejections() {
+        let rejected = TranscriptionError::ApiError(
+            "invalid_request_error: Invalid parameter: 'response_format' of type 'json_schema' is not supported with this model. (param: response_format)".to_string(),
+        );
+        assert!(rejects_response_format(&rejected));
+        assert!(!rejects_response_format(&TranscriptionError::ApiError("invalid_request_error: Invalid model".to_string())));
+        assert!(!rejects_response_format(&TranscriptionError::MissingApiKey));
+    }
+}
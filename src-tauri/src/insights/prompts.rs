@@ -0,0 +1,78 @@
+use serde_json::{json, Value};
+
+/// Placeholder replaced with the transcript text in every template.
+pub const TRANSCRIPT_PLACEHOLDER: &str = "{transcript}";
+
+const SUMMARY: &str = "Summarize the following transcript in 3‑5 bullet points. Focus on key points, decisions, and action items. Transcript:\n\n{transcript}";
+
+const ACTIONS: &str = "From the transcript below, generate 3-5 concise follow-up action items. Each title should be <= 7 words, description <= 16 words. Respond ONLY with a JSON array of objects with keys 'title' and 'description'. No extra text. Transcript:\n\n{transcript}";
+
+const TOPICS: &str = "Extract 6-10 key topics from the transcript below. Return short tags (1-3 words), no sentences, no verbs. Respond ONLY with a JSON array of strings. No extra text. Transcript:\n\n{transcript}";
+
+const COMBINED: &str = "Analyze the transcript below and respond with a JSON object with these fields:\n\
+- \"summary\": the transcript summarized in 3-5 bullet points (one string, bullets separated by newlines), focusing on key points, decisions, and action items.\n\
+- \"actions\": 3-5 concise follow-up action items, each an object with \"title\" (<= 7 words) and \"description\" (<= 16 words).\n\
+- \"topics\": 6-10 key topics as short tags (1-3 words), no sentences, no verbs.\n\
+Transcript:\n\n{transcript}";
+
+const REDUCE: &str = "The notes below were extracted from consecutive parts of one long transcript. Merge them into a single result and respond with a JSON object with these fields:\n\
+- \"summary\": the whole transcript summarized in 3-5 bullet points (one string, bullets separated by newlines), focusing on key points, decisions, and action items.\n\
+- \"actions\": the 3-5 most important follow-up action items, deduplicated, each an object with \"title\" (<= 7 words) and \"description\" (<= 16 words).\n\
+- \"topics\": 6-10 key topics as short tags (1-3 words), deduplicated.\n\
+Notes:\n\n{transcript}";
+
+#[derive(Debug, Clone)]
+pub struct PromptTemplates {
+    pub summary: String,
+    pub actions: String,
+    pub topics: String,
+    /// Asks for summary, actions and topics in one structured response.
+    pub combined: String,
+    /// Merges per-chunk results of a long transcript; same response schema
+    /// as `combined`.
+    pub reduce: String,
+}
+
+impl Default for PromptTemplates {
+    fn default() -> Self {
+        Self {
+            summary: SUMMARY.to_string(),
+            actions: ACTIONS.to_string(),
+            topics: TOPICS.to_string(),
+            combined: COMBINED.to_string(),
+            reduce: REDUCE.to_string(),
+        }
+    }
+}
+
+pub fn render(template: &str, transcript: &str) -> String {
+    template.replace(TRANSCRIPT_PLACEHOLDER, transcript)
+}
+
+/// JSON schema for the combined response, matching `GeneratedInsights`.
+pub fn combined_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "summary": { "type": "string" },
+            "actions": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "description": { "type": "string" }
+                    },
+                    "required": ["title", "description"],
+                    "additionalProperties": false
+                }
+            },
+            "topics": {
+                "type": "array",
+                "items": { "type": "string" }
+            }
+        },
+        "required": ["summary", "actions", "topics"],
+        "additionalProperties": false
+    })
+}
@@ -0,0 +1,578 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::commands::transcription;
+use crate::storage;
+
+/// Event emitted on every job state change, payload is a `JobInfo`.
+pub const JOB_EVENT: &str = "job-updated";
+
+// Failed jobs are resumed on startup until they have failed this many times
+const MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Transcription,
+    Insights,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobInfo {
+    pub id: String,
+    pub kind: JobKind,
+    pub path: String,
+    pub status: JobStatus,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub finished_at: Option<i64>,
+    /// Number of times the job has been started, across restarts.
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+struct JobEntry {
+    info: JobInfo,
+    handle: Option<JoinHandle<()>>,
+}
+
+struct QueueState {
+    // Kept in enqueue order, which is also the execution order
+    jobs: Vec<JobEntry>,
+    running: usize,
+    concurrency: usize,
+}
+
+impl QueueState {
+    fn push(&mut self, info: JobInfo) {
+        self.jobs.push(JobEntry { info, handle: None });
+    }
+
+    /// Marks queued jobs as running until the concurrency limit is reached and
+    /// returns their indices; the caller spawns them.
+    fn start_queued(&mut self) -> Vec<usize> {
+        let mut started = Vec::new();
+        while self.running < self.concurrency {
+            let Some(index) = self.jobs.iter().position(|j| j.info.status == JobStatus::Queued) else {
+                break;
+            };
+            self.running += 1;
+            let info = &mut self.jobs[index].info;
+            info.status = JobStatus::Running;
+            info.attempts += 1;
+            started.push(index);
+        }
+        started
+    }
+
+    /// Cancels a queued or running job, `None` if it is unknown or finished.
+    fn cancel(&mut self, job_id: &str) -> Option<JobInfo> {
+        let entry = self.jobs.iter_mut().find(|j| j.info.id == job_id)?;
+        let was_running = match entry.info.status {
+            JobStatus::Queued => false,
+            JobStatus::Running => true,
+            _ => return None,
+        };
+
+        // Aborting drops the task at its next await point; work already handed
+        // to spawn_blocking (local whisper) finishes but its result is discarded
+        if let Some(handle) = entry.handle.take() {
+            handle.abort();
+        }
+        entry.info.status = JobStatus::Cancelled;
+        entry.info.finished_at = Some(crate::now_unix());
+        let info = entry.info.clone();
+        if was_running {
+            self.running -= 1;
+        }
+        Some(info)
+    }
+
+    /// Records the result of a running job, `None` if it is no longer running.
+    fn finish(&mut self, job_id: &str, result: Result<(), String>) -> Option<JobInfo> {
+        let entry = self.jobs.iter_mut().find(|j| j.info.id == job_id)?;
+        // A cancelled job already released its slot
+        if entry.info.status != JobStatus::Running {
+            return None;
+        }
+
+        entry.handle = None;
+        entry.info.finished_at = Some(crate::now_unix());
+        match result {
+            Ok(()) => entry.info.status = JobStatus::Done,
+            Err(e) => {
+                warn!("Job {} failed: {}", job_id, e);
+                entry.info.status = JobStatus::Failed;
+                entry.info.error = Some(e);
+            }
+        }
+        let info = entry.info.clone();
+        self.running -= 1;
+        Some(info)
+    }
+
+    /// Queued, running and failed jobs; what the journal keeps.
+    fn journaled(&self) -> Vec<&JobInfo> {
+        self.jobs
+            .iter()
+            .map(|j| &j.info)
+            .filter(|info| matches!(info.status, JobStatus::Queued | JobStatus::Running | JobStatus::Failed))
+            .collect()
+    }
+}
+
+/// Runs transcription and insight jobs in the background with bounded
+/// concurrency. Unfinished and failed jobs are journaled to `jobs.json` under
+/// the storage root and resumed on the next start.
+///
+/// Registered as Tauri state in `run()`; commands get it via `State<JobQueue>`.
+#[derive(Clone)]
+pub struct JobQueue {
+    state: Arc<Mutex<QueueState>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl JobQueue {
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(QueueState {
+                jobs: Vec::new(),
+                running: 0,
+                concurrency: concurrency.max(1),
+            })),
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn set_concurrency(&self, app: &AppHandle, concurrency: usize) {
+        if let Ok(mut state) = self.state.lock() {
+            state.concurrency = concurrency.max(1);
+        }
+        self.pump(app);
+    }
+
+    pub fn enqueue(&self, app: &AppHandle, kind: JobKind, path: PathBuf) -> String {
+        let id = format!("{}-{}", crate::now_unix(), self.next_id.fetch_add(1, Ordering::Relaxed));
+        let info = JobInfo {
+            id: id.clone(),
+            kind,
+            path: path.to_string_lossy().to_string(),
+            status: JobStatus::Queued,
+            error: None,
+            created_at: crate::now_unix(),
+            finished_at: None,
+            attempts: 0,
+        };
+
+        self.push(app, info);
+        self.pump(app);
+        id
+    }
+
+    fn push(&self, app: &AppHandle, info: JobInfo) {
+        if let Ok(mut state) = self.state.lock() {
+            state.push(info.clone());
+            persist(app, &state);
+        }
+        emit(app, &info);
+    }
+
+    /// Re-queues unfinished and failed jobs recorded in the journal by a
+    /// previous session.
+    pub fn resume_from_journal(&self, app: &AppHandle) {
+        let journal = match journal_path(app).and_then(|path| read_journal(&path)) {
+            Ok(journal) => journal,
+            Err(e) => {
+                warn!("Failed to read job journal: {}", e);
+                return;
+            }
+        };
+
+        let (restored, resumed) = restore(journal, |info| skip_reason(app, info));
+
+        // Push everything under one lock so the journal is rewritten once
+        if let Ok(mut state) = self.state.lock() {
+            for info in &restored {
+                state.push(info.clone());
+            }
+            persist(app, &state);
+        }
+        for info in &restored {
+            emit(app, info);
+        }
+
+        if resumed > 0 {
+            info!("Resuming {} jobs from journal", resumed);
+        }
+        self.pump(app);
+    }
+
+    /// True while any job is queued or running.
+    pub fn has_unfinished(&self) -> bool {
+        match self.state.lock() {
+            Ok(state) => state.jobs.iter().any(|j| !j.info.status.is_finished()),
+            Err(_) => false,
+        }
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        match self.state.lock() {
+            Ok(state) => state.jobs.iter().map(|j| j.info.clone()).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Cancels a queued or running job. Returns false if the job is unknown or
+    /// already finished.
+    pub fn cancel(&self, app: &AppHandle, job_id: &str) -> bool {
+        let info = {
+            let Ok(mut state) = self.state.lock() else {
+                return false;
+            };
+            let Some(info) = state.cancel(job_id) else {
+                return false;
+            };
+            persist(app, &state);
+            info
+        };
+
+        info!("Cancelled job {}", job_id);
+        emit(app, &info);
+        self.pump(app);
+        true
+    }
+
+    pub fn clear_finished(&self, app: &AppHandle) {
+        if let Ok(mut state) = self.state.lock() {
+            state.jobs.retain(|j| !j.info.status.is_finished());
+            persist(app, &state);
+        }
+    }
+
+    /// Starts queued jobs until the concurrency limit is reached.
+    fn pump(&self, app: &AppHandle) {
+        let mut started = Vec::new();
+
+        if let Ok(mut state) = self.state.lock() {
+            for index in state.start_queued() {
+                let entry = &mut state.jobs[index];
+                let info = entry.info.clone();
+
+                let queue = self.clone();
+                let task_app = app.clone();
+                let job = info.clone();
+                entry.handle = Some(tauri::async_runtime::spawn(async move {
+                    let result = run_job(&task_app, &job).await;
+                    queue.finish(&task_app, &job.id, result);
+                }));
+                started.push(info);
+            }
+            if !started.is_empty() {
+                persist(app, &state);
+            }
+        }
+
+        for info in started {
+            emit(app, &info);
+        }
+    }
+
+    fn finish(&self, app: &AppHandle, job_id: &str, result: Result<(), String>) {
+        let info = {
+            let Ok(mut state) = self.state.lock() else {
+                return;
+            };
+            let Some(info) = state.finish(job_id, result) else {
+                return;
+            };
+            persist(app, &state);
+            info
+        };
+
+        emit(app, &info);
+        self.pump(app);
+    }
+}
+
+fn journal_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::get_storage_root(app)?.join("jobs.json"))
+}
+
+fn read_journal(path: &Path) -> Result<Vec<JobInfo>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn write_journal(path: &Path, jobs: &[&JobInfo]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(jobs).map_err(|e| e.to_string())?;
+    // Write then rename so a crash never leaves a truncated journal
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+/// Writes queued, running and failed jobs to `<storage root>/jobs.json`.
+/// Called with the queue lock held so writes happen in state order.
+fn persist(app: &AppHandle, state: &QueueState) {
+    let result = journal_path(app).and_then(|path| write_journal(&path, &state.journaled()));
+    if let Err(e) = result {
+        warn!("Failed to write job journal: {}", e);
+    }
+}
+
+/// Jobs of a previous session to show again, and how many of them are
+/// queued to run. Jobs that failed `MAX_ATTEMPTS` times stay failed; others
+/// are queued unless `skip_reason` gives a reason not to.
+fn restore(
+    journal: Vec<JobInfo>,
+    skip_reason: impl Fn(&JobInfo) -> Option<&'static str>,
+) -> (Vec<JobInfo>, usize) {
+    let mut restored = Vec::new();
+    let mut resumed = 0;
+    for mut info in journal {
+        if info.status == JobStatus::Failed && info.attempts >= MAX_ATTEMPTS {
+            // Keep it visible so the user can see why it stopped
+            restored.push(info);
+            continue;
+        }
+        if let Some(reason) = skip_reason(&info) {
+            info!("Not resuming job {} for {}: {}", info.id, info.path, reason);
+            continue;
+        }
+
+        info.status = JobStatus::Queued;
+        info.error = None;
+        info.finished_at = None;
+        restored.push(info);
+        resumed += 1;
+    }
+    (restored, resumed)
+}
+
+fn skip_reason(app: &AppHandle, info: &JobInfo) -> Option<&'static str> {
+    let path = PathBuf::from(&info.path);
+    if !path.exists() {
+        return Some("source file no longer exists");
+    }
+    if info.kind == JobKind::Transcription && storage::has_managed_transcript(app, &path) {
+        return Some("transcript already exists");
+    }
+    None
+}
+
+async fn run_job(app: &AppHandle, job: &JobInfo) -> Result<(), String> {
+    let path = PathBuf::from(&job.path);
+    match job.kind {
+        JobKind::Transcription => transcription::transcribe_audio_inner(app.clone(), path)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        JobKind::Insights => {
+            let stored = transcription::read_transcript_inner(app.clone(), path.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            transcription::get_transcript_insights_inner(app.clone(), stored.transcript.text, Some(&path))
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn emit(app: &AppHandle, info: &JobInfo) {
+    if let Err(e) = app.emit(JOB_EVENT, info) {
+        warn!("Failed to emit job event: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn enqueue_transcriptions(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    paths: Vec<PathBuf>,
+) -> Result<Vec<String>, String> {
+    Ok(paths
+        .into_iter()
+        .map(|path| queue.enqueue(&app, JobKind::Transcription, path))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn enqueue_insights(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    paths: Vec<PathBuf>,
+) -> Result<Vec<String>, String> {
+    Ok(paths
+        .into_iter()
+        .map(|path| queue.enqueue(&app, JobKind::Insights, path))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn cancel_job(app: AppHandle, queue: State<'_, JobQueue>, job_id: String) -> Result<bool, String> {
+    Ok(queue.cancel(&app, &job_id))
+}
+
+#[tauri::command]
+pub async fn list_jobs(queue: State<'_, JobQueue>) -> Result<Vec<JobInfo>, String> {
+    Ok(queue.list())
+}
+
+#[tauri::command]
+pub async fn clear_finished_jobs(app: AppHandle, queue: State<'_, JobQueue>) -> Result<(), String> {
+    queue.clear_finished(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    fn job(id: &str, status: JobStatus, attempts: u32) -> JobInfo {
+        JobInfo {
+            id: id.to_string(),
+            kind: JobKind::Transcription,
+            path: format!("/rec/{}.mp3", id),
+            status,
+            error: None,
+            created_at: 0,
+            finished_at: None,
+            attempts,
+        }
+    }
+
+    fn queue(concurrency: usize, ids: &[&str]) -> QueueState {
+        let mut state = QueueState {
+            jobs: Vec::new(),
+            running: 0,
+            concurrency,
+        };
+        for id in ids {
+            state.push(job(id, JobStatus::Queued, 0));
+        }
+        state
+    }
+
+    fn statuses(state: &QueueState) -> Vec<JobStatus> {
+        state.jobs.iter().map(|j| j.info.status).collect()
+    }
+
+    #[test]
+    fn starts_jobs_in_order_up_to_the_concurrency_limit() {
+        let mut state = queue(2, &["a", "b", "c"]);
+
+        assert_eq!(state.start_queued(), vec![0, 1]);
+        assert_eq!(state.start_queued(), Vec::<usize>::new());
+        assert_eq!(statuses(&state), vec![JobStatus::Running, JobStatus::Running, JobStatus::Queued]);
+
+        let failed = state.finish("a", Err("boom".to_string())).unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("boom"));
+        assert_eq!(state.start_queued(), vec![2]);
+        assert!(state.finish("b", Ok(())).is_some());
+        assert!(state.finish("b", Ok(())).is_none());
+
+        assert_eq!(state.running, 1);
+        assert_eq!(statuses(&state), vec![JobStatus::Failed, JobStatus::Done, JobStatus::Running]);
+        assert!(state.jobs.iter().all(|j| j.info.attempts == 1));
+    }
+
+    #[test]
+    fn cancelling_frees_the_slot_of_running_jobs_only() {
+        let mut state = queue(1, &["a", "b", "c"]);
+        state.start_queued();
+
+        assert_eq!(state.cancel("b").unwrap().status, JobStatus::Cancelled);
+        assert_eq!(state.running, 1);
+        assert!(state.cancel("a").is_some());
+        assert_eq!(state.running, 0);
+        // The aborted task may still report back
+        assert!(state.finish("a", Ok(())).is_none());
+        assert!(state.cancel("a").is_none());
+        assert!(state.cancel("unknown").is_none());
+
+        assert_eq!(state.start_queued(), vec![2]);
+        assert_eq!(statuses(&state), vec![JobStatus::Cancelled, JobStatus::Cancelled, JobStatus::Running]);
+    }
+
+    #[test]
+    fn resumes_failed_jobs_until_max_attempts() {
+        let mut failed = job("failed", JobStatus::Failed, 1);
+        failed.error = Some("timeout".to_string());
+        failed.finished_at = Some(1);
+        let journal = vec![
+            failed,
+            job("exhausted", JobStatus::Failed, MAX_ATTEMPTS),
+            job("running", JobStatus::Running, 1),
+            job("gone", JobStatus::Queued, 0),
+        ];
+
+        let (restored, resumed) = restore(journal, |info| (info.id == "gone").then_some("source file no longer exists"));
+
+        assert_eq!(resumed, 2);
+        let ids: Vec<&str> = restored.iter().map(|info| info.id.as_str()).collect();
+        assert_eq!(ids, vec!["failed", "exhausted", "running"]);
+        assert_eq!(restored[0].status, JobStatus::Queued);
+        assert_eq!(restored[0].error, None);
+        assert_eq!(restored[0].finished_at, None);
+        assert_eq!(restored[1].status, JobStatus::Failed);
+        assert_eq!(restored[2].status, JobStatus::Queued);
+    }
+
+    #[test]
+    fn journal_round_trips_unfinished_and_failed_jobs() {
+        let dir = scratch_dir("jobs-journal");
+        let path = dir.join("jobs.json");
+        assert!(read_journal(&path).unwrap().is_empty());
+
+        let mut state = queue(1, &["a", "b", "c"]);
+        state.start_queued();
+        state.finish("a", Err("boom".to_string()));
+        state.start_queued();
+        state.cancel("b");
+        state.push(job("d", JobStatus::Queued, 0));
+        write_journal(&path, &state.journaled()).unwrap();
+
+        let journal = read_journal(&path).unwrap();
+        let ids: Vec<&str> = journal.iter().map(|info| info.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c", "d"]);
+        assert_eq!(journal[0].error.as_deref(), Some("boom"));
+        assert_eq!(journal[0].attempts, 1);
+        assert!(!path.with_extension("json.tmp").exists());
+
+        // Journals from before attempts were counted
+        fs::write(&path, r#"[{"id":"x","kind":"insights","path":"/r.mp3","status":"failed","error":null,"createdAt":0,"finishedAt":null}]"#).unwrap();
+        assert_eq!(read_journal(&path).unwrap()[0].attempts, 0);
+    }
+}
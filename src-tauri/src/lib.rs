@@ -1,20 +1,25 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
-use symphonia::default::get_probe;
 use tauri_plugin_http;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager, State};
 
+use audio::recorded_at::RecordedAtSource;
+
+mod audio;
 mod commands;
+mod insights;
+pub mod jobs;
+pub mod library;
+mod retry;
+pub mod settings;
 pub mod storage;
+mod transcription;
+pub mod watcher;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AudioItem {
     id: String,
     name: String,
@@ -22,6 +27,15 @@ pub struct AudioItem {
     size: u64,
     mtime: i64,
     duration: Option<f64>,
+    /// Duration extrapolated from a partly unreadable file.
+    duration_estimated: bool,
+    #[serde(flatten)]
+    format: audio::AudioFormat,
+    #[serde(flatten)]
+    tags: audio::AudioTags,
+    /// Start of the recording, `mtime` when metadata and file name have none.
+    recorded_at: i64,
+    recorded_at_source: RecordedAtSource,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -59,7 +73,10 @@ pub struct InsightsRecordingRow {
     name: String,
     path: String,
     mtime_unix: i64,
+    recorded_at_unix: i64,
+    recorded_at_source: RecordedAtSource,
     duration_seconds: Option<f64>,
+    duration_estimated: bool,
     has_transcript: bool,
     language: String,
 }
@@ -119,73 +136,15 @@ async fn pick_folder(app: tauri::AppHandle) -> Result<String, String> {
 }
 
 #[tauri::command]
-fn scan_folder_for_audio(folder_path: String) -> Result<Vec<AudioItem>, String> {
-    let path = PathBuf::from(&folder_path);
-    let mut items = Vec::new();
-    
-    scan_recursive(&path, &mut items)?;
-    Ok(items)
-}
-
-fn scan_recursive(dir: &PathBuf, items: &mut Vec<AudioItem>) -> Result<(), String> {
-    let entries = fs::read_dir(dir).map_err(|e| e.to_string())?;
-    
-    for entry in entries {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let path = entry.path();
-        
-        if path.is_dir() {
-            let _ = scan_recursive(&path, items);
-        } else if let Some(ext) = path.extension() {
-            let ext = ext.to_string_lossy().to_lowercase();
-            if ext == "mp3" || ext == "m4a" || ext == "wav" {
-                if let Ok(metadata) = fs::metadata(&path) {
-                    let path_str = path.to_string_lossy().to_string();
-                    let duration = read_audio_duration(&path);
-                    items.push(AudioItem {
-                        id: format!("{:x}", md5::compute(&path_str)),
-                        name: path.file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string(),
-                        path: path_str,
-                        size: metadata.len(),
-                        mtime: metadata.modified()
-                            .ok()
-                            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                            .map(|d| d.as_secs() as i64)
-                            .unwrap_or(0),
-                        duration,
-                    });
-                }
-            }
-        }
-    }
-    
-    Ok(())
-}
-
-fn read_audio_duration(path: &PathBuf) -> Option<f64> {
-    let source = fs::File::open(path).ok()?;
-    let mss = MediaSourceStream::new(Box::new(source), Default::default());
-    let mut hint = Hint::new();
-
-    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
-        hint.with_extension(ext);
-    }
-
-    let probed = get_probe()
-        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
-        .ok()?;
-    let format = probed.format;
-    let track = format
-        .default_track()
-        .or_else(|| format.tracks().first())?;
-    let n_frames = track.codec_params.n_frames?;
-    let time_base = track.codec_params.time_base?;
-    let time = time_base.calc_time(n_frames);
-
-    Some(time.seconds as f64 + time.frac)
+async fn scan_folder_for_audio(app: AppHandle, folder_path: String) -> Result<Vec<AudioItem>, String> {
+    // Probing new files reads their headers; keep that off the main thread
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app.state::<library::LibraryIndex>();
+        let recordings = index.sync_folder(&app, Path::new(&folder_path))?;
+        Ok(recordings.iter().map(|r| r.to_audio_item()).collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 fn now_unix() -> i64 {
@@ -210,6 +169,7 @@ fn preset_min_mtime(preset: &str, now: i64) -> Result<Option<i64>, String> {
 #[tauri::command]
 fn get_library_insights(
     app: AppHandle,
+    index: State<'_, library::LibraryIndex>,
     folder_path: String,
     preset: String,
     transcription_meta_by_path: Option<HashMap<String, TranscriptionMetaItem>>,
@@ -217,9 +177,7 @@ fn get_library_insights(
     let now = now_unix();
     let min_mtime = preset_min_mtime(&preset, now)?;
 
-    let scan_path = PathBuf::from(&folder_path);
-    let mut items = Vec::new();
-    scan_recursive(&scan_path, &mut items)?;
+    let items = index.sync_folder(&app, Path::new(&folder_path))?;
 
     let mut kpi_total_recordings = 0_u64;
     let mut kpi_total_recording_seconds = 0_f64;
@@ -260,50 +218,37 @@ fn get_library_insights(
     let mut recent_rows: Vec<InsightsRecordingRow> = Vec::new();
 
     for item in items {
+        // Bucketed by when the recording was made; mtime changes on every copy or sync
+        let recorded_at = item.recorded_at_or_mtime();
         if let Some(min) = min_mtime {
-            if item.mtime < min {
+            if recorded_at.unix < min {
                 continue;
             }
         }
 
         let duration_seconds = item.duration.unwrap_or(0.0);
-        let audio_path = PathBuf::from(&item.path);
-        let has_transcript = match crate::storage::get_managed_path(&app, &audio_path) {
-            Ok(managed_dir) => {
-                let managed_transcript = managed_dir.join("transcript.txt");
-                if managed_transcript.exists() {
-                    true
-                } else {
-                    audio_path.with_extension("txt").exists()
-                }
-            }
-            Err(_) => audio_path.with_extension("txt").exists(),
-        };
+        let has_transcript = item.has_transcript;
 
         let meta = transcription_meta_by_path
             .as_ref()
             .and_then(|m| m.get(&item.path));
 
         let language = if has_transcript {
-            meta.map(|m| m.language.clone()).unwrap_or_else(|| "unknown".to_string())
+            meta.map(|m| m.language.clone())
+                .or_else(|| item.language.clone())
+                .unwrap_or_else(|| "unknown".to_string())
         } else {
             "unknown".to_string()
         };
         let language = language.to_lowercase();
 
         let transcribed_seconds = if has_transcript {
-            if let Some(m) = meta {
-                if let Some(s) = m.transcription_seconds {
-                    s
-                } else if item.duration.is_some() {
-                    duration_seconds
-                } else {
-                    0.0
-                }
+            if let Some(s) = meta.and_then(|m| m.transcription_seconds) {
+                s
             } else if item.duration.is_some() {
                 duration_seconds
             } else {
-                0.0
+                item.transcript_seconds.unwrap_or(0.0)
             }
         } else {
             0.0
@@ -317,7 +262,7 @@ fn get_library_insights(
             kpi_transcribed_seconds += transcribed_seconds;
         }
 
-        let day_start_unix = item.mtime - (item.mtime % 86400);
+        let day_start_unix = recorded_at.unix - recorded_at.unix.rem_euclid(86400);
         let entry = series_by_day.entry(day_start_unix).or_insert((0.0, 0.0, 0));
         entry.0 += duration_seconds;
         entry.1 += transcribed_seconds;
@@ -335,12 +280,7 @@ fn get_library_insights(
         duration_buckets[bucket_idx].count += 1;
         duration_buckets[bucket_idx].seconds += duration_seconds;
 
-        let ext = PathBuf::from(&item.path)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-        let ext = if ext.is_empty() { "unknown".to_string() } else { ext };
+        let ext = if item.ext.is_empty() { "unknown".to_string() } else { item.ext.clone() };
         let ext_entry = file_type_dist.entry(ext).or_insert((0, 0.0));
         ext_entry.0 += 1;
         ext_entry.1 += duration_seconds;
@@ -352,17 +292,20 @@ fn get_library_insights(
         }
 
         recent_rows.push(InsightsRecordingRow {
-            id: item.id,
+            id: format!("{:x}", md5::compute(&item.path)),
             name: item.name,
             path: item.path,
             mtime_unix: item.mtime,
+            recorded_at_unix: recorded_at.unix,
+            recorded_at_source: recorded_at.source,
             duration_seconds: item.duration,
+            duration_estimated: item.duration_estimated,
             has_transcript,
             language,
         });
     }
 
-    recent_rows.sort_by(|a, b| b.mtime_unix.cmp(&a.mtime_unix));
+    recent_rows.sort_by(|a, b| b.recorded_at_unix.cmp(&a.recorded_at_unix));
     if recent_rows.len() > 10 {
         recent_rows.truncate(10);
     }
@@ -420,26 +363,9 @@ fn get_library_insights(
 }
 
 #[tauri::command]
-fn read_file_meta(file_path: String) -> Result<AudioItem, String> {
-    let path = PathBuf::from(&file_path);
-    let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
-    let duration = read_audio_duration(&path);
-    
-    Ok(AudioItem {
-        id: format!("{:x}", md5::compute(&file_path)),
-        name: path.file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string(),
-        path: file_path,
-        size: metadata.len(),
-        mtime: metadata.modified()
-            .ok()
-            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0),
-        duration,
-    })
+fn read_file_meta(app: AppHandle, index: State<'_, library::LibraryIndex>, file_path: String) -> Result<AudioItem, String> {
+    let recording = index.refresh_file(&app, Path::new(&file_path))?;
+    Ok(recording.to_audio_item())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -477,6 +403,18 @@ pub fn run() {
             .build(),
         )?;
       }
+      let app_settings = settings::load_settings(app.handle()).unwrap_or_default();
+      let queue = jobs::JobQueue::new(app_settings.job_concurrency);
+      app.manage(queue.clone());
+      app.manage(insights::EngineCache::default());
+      storage::migrate_legacy_cache(app.handle());
+      let migrate_app = app.handle().clone();
+      tauri::async_runtime::spawn_blocking(move || storage::migrate_cached_insights(&migrate_app));
+      app.manage(library::open_for_app(app.handle())?);
+      queue.resume_from_journal(app.handle());
+      let watcher = watcher::LibraryWatcher::default();
+      watcher.watch(app.handle(), &app_settings);
+      app.manage(watcher);
       Ok(())
     })
     .invoke_handler(tauri::generate_handler![
@@ -484,13 +422,29 @@ pub fn run() {
         scan_folder_for_audio,
         read_file_meta,
         get_library_insights,
+        settings::get_settings,
+        settings::update_settings,
+        settings::reset_settings,
         storage::get_storage_root_command,
         storage::get_insights_dir_command,
         storage::ensure_audio_dir_command,
         storage::list_managed_recordings,
+        storage::relink_managed_storage,
+        storage::get_recording_manifest,
+        storage::move_storage_root,
+        storage::get_storage_usage,
+        storage::clean_storage,
+        storage::export_library,
+        storage::import_library,
         storage::get_transcript_path,
         commands::transcription::transcribe_audio,
+        commands::waveform::get_waveform_peaks,
         commands::transcription::transcribe_audio_batch,
+        jobs::enqueue_transcriptions,
+        jobs::enqueue_insights,
+        jobs::cancel_job,
+        jobs::list_jobs,
+        jobs::clear_finished_jobs,
         commands::transcription::save_transcript,
         commands::transcription::save_export,
         commands::transcription::read_transcript,
@@ -0,0 +1,796 @@
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::audio::recorded_at::{RecordedAt, RecordedAtSource};
+use crate::audio::{self, formats, AudioFormat, AudioTags, PROBE_VERSION};
+use crate::commands::transcription;
+use crate::storage;
+use crate::AudioItem;
+
+// Bump together with a new step in `migrate`
+const SCHEMA_VERSION: i32 = 5;
+
+const COLUMNS: &str = "path, name, ext, size, mtime, duration, has_transcript, language, transcript_seconds, \
+    content_hash, duration_estimated, probe_version, codec, sample_rate, channels, bitrate, title, artist, date, \
+    comment, has_cover_art, recorded_at, recorded_at_source";
+
+/// A recording as stored in the library index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedRecording {
+    pub path: String,
+    pub name: String,
+    pub ext: String,
+    pub size: u64,
+    pub mtime: i64,
+    pub duration: Option<f64>,
+    pub has_transcript: bool,
+    pub language: Option<String>,
+    /// Duration covered by the transcript, from `transcript.json`.
+    pub transcript_seconds: Option<f64>,
+    /// BLAKE3 of the audio stream; identifies the managed directory.
+    pub content_hash: Option<String>,
+    /// `duration` was extrapolated from a partly unreadable file.
+    pub duration_estimated: bool,
+    /// `PROBE_VERSION` that produced the probe fields.
+    pub probe_version: i32,
+    pub format: AudioFormat,
+    pub tags: AudioTags,
+    /// Start time from metadata or the file name, `None` if neither has one.
+    pub recorded_at: Option<RecordedAt>,
+}
+
+impl IndexedRecording {
+    /// `recorded_at`, or the modification time when it is unknown.
+    pub fn recorded_at_or_mtime(&self) -> RecordedAt {
+        self.recorded_at.unwrap_or(RecordedAt {
+            unix: self.mtime,
+            source: RecordedAtSource::Mtime,
+        })
+    }
+
+    pub fn to_audio_item(&self) -> AudioItem {
+        let recorded_at = self.recorded_at_or_mtime();
+        AudioItem {
+            id: format!("{:x}", md5::compute(&self.path)),
+            name: self.name.clone(),
+            path: self.path.clone(),
+            size: self.size,
+            mtime: self.mtime,
+            duration: self.duration,
+            duration_estimated: self.duration_estimated,
+            format: self.format.clone(),
+            tags: self.tags.clone(),
+            recorded_at: recorded_at.unix,
+            recorded_at_source: recorded_at.source,
+        }
+    }
+}
+
+/// Database file of the library index under the storage root.
+pub const LIBRARY_DB_FILE: &str = "library.db";
+
+/// Persistent catalog of recordings in `<storage root>/library.db`.
+///
+/// Scans only stat the files; symphonia probes and transcript reads happen
+/// for files that are new or whose size/mtime changed since the last scan.
+/// Registered as Tauri state in `run()`.
+pub struct LibraryIndex {
+    conn: Mutex<Connection>,
+    // Content hashes of files without an up-to-date row, keyed by path and
+    // checked against size and mtime; lives for the session only
+    hashes: Mutex<HashMap<String, (u64, i64, String)>>,
+}
+
+impl LibraryIndex {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        Self::with_connection(conn)
+    }
+
+    /// Index that lives only for this session, used when the database file
+    /// can't be opened.
+    pub fn in_memory() -> Result<Self, String> {
+        Self::with_connection(Connection::open_in_memory().map_err(|e| e.to_string())?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, String> {
+        migrate(&conn).map_err(|e| e.to_string())?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            hashes: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Writes a consistent copy of the database to `path`, which must not exist.
+    pub fn backup_to(&self, path: &Path) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Switches to the database at `path`, e.g. after the storage root moved.
+    pub fn reopen(&self, path: &Path) -> Result<(), String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        migrate(&conn).map_err(|e| e.to_string())?;
+        *self.conn.lock().map_err(|e| e.to_string())? = conn;
+        Ok(())
+    }
+
+    /// Brings the index for `folder` up to date with the filesystem and
+    /// returns its recordings.
+    pub fn sync_folder(&self, app: &AppHandle, folder: &Path) -> Result<Vec<IndexedRecording>, String> {
+        let mut files = Vec::new();
+        walk_audio_files(folder, &mut files)?;
+
+        let known: HashMap<String, IndexedRecording> = self
+            .recordings_under(folder)?
+            .into_iter()
+            .map(|r| (r.path.clone(), r))
+            .collect();
+
+        // Probe outside the lock; this is the slow part
+        let mut probed = 0;
+        // Only read when a file without a hash turns up
+        let by_source = OnceCell::new();
+        let find_unhashed = |path: &PathBuf| {
+            let by_source = by_source.get_or_init(|| storage::managed_dirs_by_source(app));
+            by_source
+                .get(&*path.to_string_lossy())
+                .cloned()
+                .or_else(|| storage::legacy_managed_dir(app, path))
+        };
+        let recordings: Vec<IndexedRecording> = files
+            .into_iter()
+            .map(|(path, size, mtime)| {
+                let previous = known.get(&*path.to_string_lossy());
+                if needs_probe(previous, size, mtime) {
+                    probed += 1;
+                }
+                index_entry(app, &path, size, mtime, previous, &find_unhashed)
+            })
+            .collect();
+
+        let seen: HashSet<&str> = recordings.iter().map(|r| r.path.as_str()).collect();
+        let stale: Vec<&String> = known.keys().filter(|path| !seen.contains(path.as_str())).collect();
+
+        let mut conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for recording in &recordings {
+            upsert(&tx, recording).map_err(|e| e.to_string())?;
+        }
+        for path in &stale {
+            tx.execute("DELETE FROM recordings WHERE path = ?1", params![path])
+                .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+
+        if probed > 0 || !stale.is_empty() {
+            info!(
+                "Library index for {:?}: {} recordings, {} probed, {} removed",
+                folder,
+                recordings.len(),
+                probed,
+                stale.len()
+            );
+        }
+        Ok(recordings)
+    }
+
+    /// Indexed recordings at or below `folder`, without touching the disk.
+    pub fn recordings_under(&self, folder: &Path) -> Result<Vec<IndexedRecording>, String> {
+        let folder = folder.to_string_lossy().trim_end_matches(MAIN_SEPARATOR).to_string();
+        let pattern = format!("{}{}%", escape_like(&folder), MAIN_SEPARATOR);
+
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM recordings WHERE path LIKE ?1 ESCAPE '\\' ORDER BY path",
+                COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![pattern], row_to_recording)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    /// Returns the entry for one file, re-probing it only if it changed.
+    pub fn refresh_file(&self, app: &AppHandle, path: &Path) -> Result<IndexedRecording, String> {
+        let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+        let (size, mtime) = (metadata.len(), mtime_unix(&metadata));
+        let key = path.to_string_lossy().to_string();
+
+        let previous = {
+            let conn = self.conn.lock().map_err(|e| e.to_string())?;
+            conn.query_row(
+                &format!("SELECT {} FROM recordings WHERE path = ?1", COLUMNS),
+                params![key],
+                row_to_recording,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+        };
+
+        let find_unhashed = |path: &PathBuf| storage::find_managed_dir_by_source(app, path);
+        let recording = index_entry(app, path, size, mtime, previous.as_ref(), &find_unhashed);
+        if previous.as_ref() != Some(&recording) {
+            let conn = self.conn.lock().map_err(|e| e.to_string())?;
+            upsert(&conn, &recording).map_err(|e| e.to_string())?;
+        }
+        Ok(recording)
+    }
+
+    /// Content hash of `path`, computed only when the file is new or changed
+    /// since it was last hashed, whether or not it is indexed. Falls back to
+    /// the stored hash for files that no longer exist.
+    pub fn content_hash(&self, path: &Path) -> Result<String, String> {
+        let key = path.to_string_lossy().to_string();
+        let stored: Option<(i64, i64, Option<String>)> = {
+            let conn = self.conn.lock().map_err(|e| e.to_string())?;
+            conn.query_row(
+                "SELECT size, mtime, content_hash FROM recordings WHERE path = ?1",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+        };
+
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                return stored
+                    .and_then(|(_, _, hash)| hash)
+                    .ok_or_else(|| format!("{}: {}", path.display(), e));
+            }
+        };
+        let (size, mtime) = (metadata.len(), mtime_unix(&metadata));
+        if let Some((stored_size, stored_mtime, Some(hash))) = &stored {
+            if *stored_size as u64 == size && *stored_mtime == mtime {
+                return Ok(hash.clone());
+            }
+        }
+        let cached = self.hashes.lock().map_err(|e| e.to_string())?.get(&key).cloned();
+        if let Some((_, _, hash)) = cached.filter(|(s, m, _)| *s == size && *m == mtime) {
+            return Ok(hash);
+        }
+
+        let hash = audio::content_hash(path)?;
+        self.hashes
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(key.clone(), (size, mtime, hash.clone()));
+        if stored.is_some() {
+            let conn = self.conn.lock().map_err(|e| e.to_string())?;
+            conn.execute(
+                "UPDATE recordings SET content_hash = ?2 WHERE path = ?1",
+                params![key, hash],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        Ok(hash)
+    }
+
+    /// Indexed paths grouped by content hash. Indexed files that were never
+    /// hashed are hashed first, which reads each of them in full.
+    pub fn paths_by_hash(&self) -> Result<HashMap<String, Vec<String>>, String> {
+        let unhashed: Vec<String> = {
+            let conn = self.conn.lock().map_err(|e| e.to_string())?;
+            let mut stmt = conn
+                .prepare("SELECT path FROM recordings WHERE content_hash IS NULL")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+        for path in unhashed.iter().map(Path::new).filter(|path| path.exists()) {
+            if let Err(e) = self.content_hash(path) {
+                warn!("Failed to hash {:?}: {}", path, e);
+            }
+        }
+
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT content_hash, path FROM recordings WHERE content_hash IS NOT NULL ORDER BY path")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?;
+
+        let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (hash, path) = row.map_err(|e| e.to_string())?;
+            groups.entry(hash).or_default().push(path);
+        }
+        Ok(groups)
+    }
+
+    pub fn contains(&self, path: &Path) -> Result<bool, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT 1 FROM recordings WHERE path = ?1",
+            params![path.to_string_lossy()],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|row| row.is_some())
+        .map_err(|e| e.to_string())
+    }
+
+    /// Removes `path` and, if it was a folder, everything indexed below it.
+    /// Returns the removed paths.
+    pub fn remove_under(&self, path: &Path) -> Result<Vec<String>, String> {
+        let mut removed: Vec<String> = self.recordings_under(path)?.into_iter().map(|r| r.path).collect();
+        let key = path.to_string_lossy().to_string();
+
+        let mut conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        if tx
+            .execute("DELETE FROM recordings WHERE path = ?1", params![key])
+            .map_err(|e| e.to_string())?
+            > 0
+        {
+            removed.push(key);
+        }
+        for removed_path in &removed {
+            tx.execute("DELETE FROM recordings WHERE path = ?1", params![removed_path])
+                .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(removed)
+    }
+
+    /// Records a finished transcription so the next listing doesn't have to
+    /// read the transcript back. No-op for files that aren't indexed yet.
+    pub fn set_transcript(&self, path: &Path, language: &str, seconds: f64) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE recordings SET has_transcript = 1, language = ?2, transcript_seconds = ?3 WHERE path = ?1",
+            params![path.to_string_lossy(), language, seconds],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version < 1 {
+        conn.execute_batch(
+            "CREATE TABLE recordings (
+                path TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                ext TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                mtime INTEGER NOT NULL,
+                duration REAL,
+                has_transcript INTEGER NOT NULL DEFAULT 0,
+                language TEXT,
+                transcript_seconds REAL
+            );
+            CREATE INDEX recordings_mtime ON recordings (mtime);",
+        )?;
+    }
+    if version < 2 {
+        conn.execute_batch(
+            "ALTER TABLE recordings ADD COLUMN content_hash TEXT;
+            CREATE INDEX recordings_content_hash ON recordings (content_hash);",
+        )?;
+    }
+    if version < 3 {
+        conn.execute_batch("ALTER TABLE recordings ADD COLUMN duration_estimated INTEGER NOT NULL DEFAULT 0;")?;
+    }
+    if version < 4 {
+        conn.execute_batch(
+            "ALTER TABLE recordings ADD COLUMN probe_version INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE recordings ADD COLUMN codec TEXT;
+            ALTER TABLE recordings ADD COLUMN sample_rate INTEGER;
+            ALTER TABLE recordings ADD COLUMN channels INTEGER;
+            ALTER TABLE recordings ADD COLUMN bitrate INTEGER;
+            ALTER TABLE recordings ADD COLUMN title TEXT;
+            ALTER TABLE recordings ADD COLUMN artist TEXT;
+            ALTER TABLE recordings ADD COLUMN date TEXT;
+            ALTER TABLE recordings ADD COLUMN comment TEXT;
+            ALTER TABLE recordings ADD COLUMN has_cover_art INTEGER NOT NULL DEFAULT 0;",
+        )?;
+    }
+    if version < 5 {
+        conn.execute_batch(
+            "ALTER TABLE recordings ADD COLUMN recorded_at INTEGER;
+            ALTER TABLE recordings ADD COLUMN recorded_at_source TEXT;",
+        )?;
+    }
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+}
+
+fn row_to_recording(row: &rusqlite::Row) -> rusqlite::Result<IndexedRecording> {
+    Ok(IndexedRecording {
+        path: row.get(0)?,
+        name: row.get(1)?,
+        ext: row.get(2)?,
+        size: row.get::<_, i64>(3)? as u64,
+        mtime: row.get(4)?,
+        duration: row.get(5)?,
+        has_transcript: row.get(6)?,
+        language: row.get(7)?,
+        transcript_seconds: row.get(8)?,
+        content_hash: row.get(9)?,
+        duration_estimated: row.get(10)?,
+        probe_version: row.get(11)?,
+        format: AudioFormat {
+            codec: row.get(12)?,
+            sample_rate: row.get(13)?,
+            channels: row.get(14)?,
+            bitrate: row.get(15)?,
+        },
+        tags: AudioTags {
+            title: row.get(16)?,
+            artist: row.get(17)?,
+            date: row.get(18)?,
+            comment: row.get(19)?,
+            has_cover_art: row.get(20)?,
+        },
+        recorded_at: match (row.get::<_, Option<i64>>(21)?, row.get::<_, Option<String>>(22)?) {
+            (Some(unix), Some(source)) => RecordedAtSource::parse(&source).map(|source| RecordedAt { unix, source }),
+            _ => None,
+        },
+    })
+}
+
+fn upsert(conn: &Connection, r: &IndexedRecording) -> rusqlite::Result<usize> {
+    conn.execute(
+        &format!(
+            "INSERT INTO recordings ({})
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)
+             ON CONFLICT (path) DO UPDATE SET
+                name = excluded.name, ext = excluded.ext, size = excluded.size, mtime = excluded.mtime,
+                duration = excluded.duration, has_transcript = excluded.has_transcript,
+                language = excluded.language, transcript_seconds = excluded.transcript_seconds,
+                content_hash = excluded.content_hash, duration_estimated = excluded.duration_estimated,
+                probe_version = excluded.probe_version, codec = excluded.codec, sample_rate = excluded.sample_rate,
+                channels = excluded.channels, bitrate = excluded.bitrate, title = excluded.title,
+                artist = excluded.artist, date = excluded.date, comment = excluded.comment,
+                has_cover_art = excluded.has_cover_art, recorded_at = excluded.recorded_at,
+                recorded_at_source = excluded.recorded_at_source",
+            COLUMNS
+        ),
+        params![
+            r.path,
+            r.name,
+            r.ext,
+            r.size as i64,
+            r.mtime,
+            r.duration,
+            r.has_transcript,
+            r.language,
+            r.transcript_seconds,
+            r.content_hash,
+            r.duration_estimated,
+            r.probe_version,
+            r.format.codec,
+            r.format.sample_rate,
+            r.format.channels,
+            r.format.bitrate,
+            r.tags.title,
+            r.tags.artist,
+            r.tags.date,
+            r.tags.comment,
+            r.tags.has_cover_art,
+            r.recorded_at.map(|r| r.unix),
+            r.recorded_at.map(|r| r.source.as_str())
+        ],
+    )
+}
+
+/// Whether the file is new, changed, or was probed by an older
+/// `PROBE_VERSION`. Files that can't be read are not retried until one of
+/// those happens.
+fn needs_probe(previous: Option<&IndexedRecording>, size: u64, mtime: i64) -> bool {
+    previous.map_or(true, |p| p.size != size || p.mtime != mtime || p.probe_version < PROBE_VERSION)
+}
+
+/// Builds the index entry for a file, reusing the previous entry's probe and
+/// transcript results when they are still valid. New and changed files are
+/// not hashed, which means reading them in full; `LibraryIndex::content_hash`
+/// does that once their managed directory is needed, and until then
+/// `find_unhashed` finds the directory by name.
+fn index_entry(
+    app: &AppHandle,
+    path: &Path,
+    size: u64,
+    mtime: i64,
+    previous: Option<&IndexedRecording>,
+    find_unhashed: &dyn Fn(&PathBuf) -> Option<PathBuf>,
+) -> IndexedRecording {
+    let path_buf = path.to_path_buf();
+    let unchanged = previous.filter(|p| p.size == size && p.mtime == mtime);
+
+    let (duration, duration_estimated, format, tags, recorded_at) = match unchanged {
+        Some(p) if !needs_probe(previous, size, mtime) => (p.duration, p.duration_estimated, p.format.clone(), p.tags.clone(), p.recorded_at),
+        _ => {
+            let probe = audio::probe(path).unwrap_or_default();
+            let duration = probe.duration;
+            (
+                duration.map(|d| d.seconds),
+                duration.is_some_and(|d| !d.exact),
+                probe.format,
+                probe.tags,
+                probe.recorded_at,
+            )
+        }
+    };
+    let content_hash = unchanged.and_then(|p| p.content_hash.clone());
+    let managed_dir = match content_hash.as_deref() {
+        Some(hash) => storage::managed_dir_for(app, &path_buf, hash).ok(),
+        None => find_unhashed(&path_buf),
+    };
+
+    let has_transcript =
+        managed_dir.as_deref().is_some_and(storage::dir_has_transcript) || path.with_extension("txt").exists();
+    let (language, transcript_seconds) = match unchanged {
+        Some(p) if p.has_transcript && has_transcript => (p.language.clone(), p.transcript_seconds),
+        _ => match &managed_dir {
+            Some(dir) if has_transcript => read_transcript_meta(dir),
+            _ => (None, None),
+        },
+    };
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    IndexedRecording {
+        path: path.to_string_lossy().to_string(),
+        name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        ext,
+        size,
+        mtime,
+        duration,
+        has_transcript,
+        language,
+        transcript_seconds,
+        content_hash,
+        duration_estimated,
+        probe_version: PROBE_VERSION,
+        format,
+        tags,
+        recorded_at,
+    }
+}
+
+fn read_transcript_meta(managed_dir: &Path) -> (Option<String>, Option<f64>) {
+    match transcription::read_transcript_json(managed_dir) {
+        Some(stored) if stored.transcript.language != "unknown" => (
+            Some(stored.transcript.language.to_lowercase()),
+            Some(stored.transcript.duration as f64).filter(|d| *d > 0.0),
+        ),
+        Some(stored) => (None, Some(stored.transcript.duration as f64).filter(|d| *d > 0.0)),
+        None => (None, None),
+    }
+}
+
+pub(crate) fn walk_audio_files(dir: &Path, files: &mut Vec<(PathBuf, u64, i64)>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| e.to_string())?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            let _ = walk_audio_files(&path, files);
+        } else if formats::is_supported(&path) {
+            if let Ok(metadata) = fs::metadata(&path) {
+                let mtime = mtime_unix(&metadata);
+                files.push((path, metadata.len(), mtime));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn mtime_unix(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Opens the index under the storage root, falling back to an in-memory one
+/// so the app still starts if the database is unusable.
+pub fn open_for_app(app: &AppHandle) -> Result<LibraryIndex, String> {
+    let opened = storage::get_storage_root(app).and_then(|root| LibraryIndex::open(&root.join(LIBRARY_DB_FILE)));
+    match opened {
+        Ok(index) => Ok(index),
+        Err(e) => {
+            warn!("Failed to open library index, using an in-memory one: {}", e);
+            LibraryIndex::in_memory()
+        }
+    }
+}
+
+/// Updates the index after a transcription, if the index is available.
+pub fn record_transcript(app: &AppHandle, path: &Path, language: &str, seconds: f64) {
+    if let Some(index) = app.try_state::<LibraryIndex>() {
+        if let Err(e) = index.set_transcript(path, &language.to_lowercase(), seconds) {
+            warn!("Failed to update library index for {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    fn recording(path: &str, size: u64) -> IndexedRecording {
+        IndexedRecording {
+            path: path.to_string(),
+            name: Path::new(path).file_name().unwrap().to_string_lossy().to_string(),
+            ext: "mp3".to_string(),
+            size,
+            mtime: 1_700_000_000,
+            duration: Some(61.5),
+            has_transcript: false,
+            language: None,
+            transcript_seconds: None,
+            content_hash: Some(format!("{:064x}", size)),
+            duration_estimated: false,
+            probe_version: PROBE_VERSION,
+            format: AudioFormat::default(),
+            tags: AudioTags::default(),
+            recorded_at: None,
+        }
+    }
+
+    #[test]
+    fn upserts_and_lists_by_folder_prefix() {
+        let index = LibraryIndex::in_memory().unwrap();
+        {
+            let conn = index.conn.lock().unwrap();
+            upsert(&conn, &recording("/rec/a.mp3", 1)).unwrap();
+            upsert(&conn, &recording("/rec/sub/b.mp3", 2)).unwrap();
+            upsert(&conn, &recording("/rec_other/c.mp3", 3)).unwrap();
+            upsert(&conn, &recording("/rec/a.mp3", 10)).unwrap();
+        }
+
+        let rows = index.recordings_under(Path::new("/rec/")).unwrap();
+
+        let paths: Vec<&str> = rows.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["/rec/a.mp3", "/rec/sub/b.mp3"]);
+        assert_eq!(rows[0].size, 10);
+        assert_eq!(rows[0].duration, Some(61.5));
+    }
+
+    #[test]
+    fn groups_paths_by_content_hash() {
+        let index = LibraryIndex::in_memory().unwrap();
+        {
+            let conn = index.conn.lock().unwrap();
+            upsert(&conn, &recording("/rec/a.mp3", 1)).unwrap();
+            upsert(&conn, &recording("/rec/copy of a.mp3", 1)).unwrap();
+            upsert(&conn, &recording("/rec/b.mp3", 2)).unwrap();
+        }
+
+        let groups = index.paths_by_hash().unwrap();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&format!("{:064x}", 1)], vec!["/rec/a.mp3", "/rec/copy of a.mp3"]);
+    }
+
+    #[test]
+    fn hashes_unhashed_files_before_grouping() {
+        let dir = scratch_dir("library-unhashed");
+        let path = dir.join("a.mp3");
+        fs::write(&path, b"not really audio").unwrap();
+        let index = LibraryIndex::in_memory().unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        let unhashed = IndexedRecording {
+            size: metadata.len(),
+            mtime: mtime_unix(&metadata),
+            content_hash: None,
+            ..recording(&path.to_string_lossy(), 1)
+        };
+        upsert(&index.conn.lock().unwrap(), &unhashed).unwrap();
+
+        let groups = index.paths_by_hash().unwrap();
+
+        let hash = audio::content_hash(&path).unwrap();
+        assert_eq!(groups[&hash], vec![unhashed.path.clone()]);
+        assert_eq!(index.recordings_under(&dir).unwrap()[0].content_hash, Some(hash));
+    }
+
+    #[test]
+    fn keeps_probe_results() {
+        let index = LibraryIndex::in_memory().unwrap();
+        let probed = IndexedRecording {
+            duration_estimated: true,
+            format: AudioFormat {
+                codec: Some("mp3".to_string()),
+                sample_rate: Some(44_100),
+                channels: Some(2),
+                bitrate: Some(128_000),
+            },
+            tags: AudioTags {
+                title: Some("Standup".to_string()),
+                has_cover_art: true,
+                ..AudioTags::default()
+            },
+            recorded_at: Some(RecordedAt {
+                unix: 1_600_000_000,
+                source: RecordedAtSource::FileName,
+            }),
+            ..recording("/rec/a.mp3", 1)
+        };
+        upsert(&index.conn.lock().unwrap(), &probed).unwrap();
+
+        let rows = index.recordings_under(Path::new("/rec")).unwrap();
+
+        assert_eq!(rows, vec![probed]);
+        assert!(rows[0].to_audio_item().duration_estimated);
+        assert_eq!(rows[0].recorded_at_or_mtime().unix, 1_600_000_000);
+        let unknown = recording("/rec/b.mp3", 2).recorded_at_or_mtime();
+        assert_eq!((unknown.unix, unknown.source), (1_700_000_000, RecordedAtSource::Mtime));
+    }
+
+    #[test]
+    fn probes_unreadable_files_once_per_probe_version() {
+        let unreadable = IndexedRecording {
+            duration: None,
+            ..recording("/rec/a.amr", 1)
+        };
+        let outdated = IndexedRecording {
+            probe_version: PROBE_VERSION - 1,
+            ..unreadable.clone()
+        };
+
+        assert!(needs_probe(None, 1, 1_700_000_000));
+        assert!(!needs_probe(Some(&unreadable), 1, 1_700_000_000));
+        assert!(needs_probe(Some(&unreadable), 2, 1_700_000_000));
+        assert!(needs_probe(Some(&outdated), 1, 1_700_000_000));
+    }
+
+    #[test]
+    fn caches_hashes_of_unindexed_files() {
+        let dir = scratch_dir("library-hash");
+        let path = dir.join("talk.mp3");
+        fs::write(&path, b"not really audio").unwrap();
+        let index = LibraryIndex::in_memory().unwrap();
+
+        let hash = index.content_hash(&path).unwrap();
+
+        let cached = index.hashes.lock().unwrap().get(&*path.to_string_lossy()).cloned();
+        assert_eq!(cached.map(|(size, _, hash)| (size, hash)), Some((16, hash.clone())));
+        assert_eq!(index.content_hash(&path).unwrap(), hash);
+    }
+
+    #[test]
+    fn records_transcripts() {
+        let index = LibraryIndex::in_memory().unwrap();
+        upsert(&index.conn.lock().unwrap(), &recording("/rec/a.mp3", 1)).unwrap();
+
+        index.set_transcript(Path::new("/rec/a.mp3"), "en", 60.0).unwrap();
+
+        let row = &index.recordings_under(Path::new("/rec")).unwrap()[0];
+        assert!(row.has_transcript);
+        assert_eq!(row.language.as_deref(), Some("en"));
+        assert_eq!(row.transcript_seconds, Some(60.0));
+    }
+}
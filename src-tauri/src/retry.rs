@@ -0,0 +1,414 @@
+use async_openai::{config::OpenAIConfig, error::OpenAIError, Client};
+use backoff::ExponentialBackoffBuilder;
+use log::warn;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::commands::transcription::TranscriptionError;
+use crate::settings::AppSettings;
+
+/// How a failed API call should be handled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorClass {
+    /// 429; wait at least `retry_after` when the server says so.
+    RateLimited { retry_after: Option<Duration> },
+    /// 5xx, timeouts and connection errors.
+    Transient,
+    /// Bad or missing credentials; retrying won't help.
+    Auth,
+    /// Account is out of credit; retrying won't help.
+    QuotaExceeded,
+    /// Any other 4xx or client-side error.
+    Permanent,
+}
+
+impl ErrorClass {
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorClass::RateLimited { .. } | ErrorClass::Transient)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Total time budget; no new attempt starts once it is spent.
+    pub max_elapsed: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            max_attempts: settings.max_retries + 1,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_elapsed: Duration::from_secs(settings.retry_budget_seconds),
+        }
+    }
+
+    /// Full-jitter exponential backoff: a random delay in
+    /// `[0, min(max_delay, base_delay * 2^attempt)]`.
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let cap = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        cap.mul_f64(jitter())
+    }
+}
+
+// Cheap randomness in [0, 1) without pulling in a rand crate: a splitmix64
+// sequence seeded once from the clock and the process id. Reading the clock
+// per call is not enough, macOS `SystemTime` only has microsecond resolution.
+fn jitter() -> f64 {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+    static SEED: OnceLock<u64> = OnceLock::new();
+
+    let seed = *SEED.get_or_init(|| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        nanos ^ (u64::from(std::process::id()) << 32)
+    });
+    let mut z = seed.wrapping_add(STATE.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    // Top 53 bits fill an f64 mantissa exactly
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Builds a client with async-openai's own retry loop disabled, so all
+/// retries go through `with_retry` and respect the configured budget.
+pub fn openai_client(config: OpenAIConfig) -> Client<OpenAIConfig> {
+    let no_retry = ExponentialBackoffBuilder::new()
+        .with_max_elapsed_time(Some(Duration::ZERO))
+        .build();
+    Client::with_config(config).with_backoff(no_retry)
+}
+
+/// An error `with_retry` knows how to classify and report.
+pub trait RetryableError: std::fmt::Display {
+    fn class(&self) -> ErrorClass;
+
+    /// Converts a final (non-retried) error into a `TranscriptionError`.
+    fn into_error(self) -> TranscriptionError;
+}
+
+impl RetryableError for OpenAIError {
+    fn class(&self) -> ErrorClass {
+        classify(self)
+    }
+
+    fn into_error(self) -> TranscriptionError {
+        into_transcription_error(self)
+    }
+}
+
+pub fn classify(err: &OpenAIError) -> ErrorClass {
+    match err {
+        OpenAIError::Reqwest(e) => {
+            if let Some(status) = e.status() {
+                return classify_status(status.as_u16(), None);
+            }
+            if e.is_timeout() || e.is_connect() || e.is_request() || e.is_body() {
+                ErrorClass::Transient
+            } else {
+                ErrorClass::Permanent
+            }
+        }
+        OpenAIError::ApiError(api) => {
+            let code = api.code.as_deref().unwrap_or("");
+            let kind = api.r#type.as_deref().unwrap_or("");
+
+            if code == "insufficient_quota" || kind == "insufficient_quota" {
+                ErrorClass::QuotaExceeded
+            } else if code == "rate_limit_exceeded" || kind == "requests" || kind == "tokens" {
+                ErrorClass::RateLimited {
+                    retry_after: parse_retry_hint(&api.message),
+                }
+            } else if code == "invalid_api_key" || kind == "authentication_error" {
+                ErrorClass::Auth
+            } else if kind == "server_error" || code == "server_error" {
+                ErrorClass::Transient
+            } else if mentions_rate_limit(&api.message) {
+                // OpenAI-compatible servers answer 429 without OpenAI's codes
+                ErrorClass::RateLimited {
+                    retry_after: parse_retry_hint(&api.message),
+                }
+            } else if api.r#type.is_none() && api.code.is_none() && looks_like_server_error(&api.message) {
+                // async-openai reports every 5xx this way, with the raw body as message
+                ErrorClass::Transient
+            } else {
+                ErrorClass::Permanent
+            }
+        }
+        // Non-JSON error bodies, e.g. a proxy's plain-text "429 Too Many Requests"
+        OpenAIError::JSONDeserialize(_, content) if mentions_rate_limit(content) => ErrorClass::RateLimited {
+            retry_after: parse_retry_hint(content),
+        },
+        _ => ErrorClass::Permanent,
+    }
+}
+
+fn mentions_rate_limit(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("rate limit") || message.contains("too many requests")
+}
+
+/// Whether an untyped API error message is a raw 5xx body rather than a parsed
+/// 4xx `error.message`: empty, HTML or unparsed JSON, or a gateway's status text.
+fn looks_like_server_error(message: &str) -> bool {
+    let message = message.trim().to_lowercase();
+    message.is_empty()
+        || message.starts_with('<')
+        || message.starts_with('{')
+        || ["internal server error", "bad gateway", "service unavailable", "gateway timeout", "upstream"]
+            .iter()
+            .any(|phrase| message.contains(phrase))
+}
+
+/// Classifies a raw HTTP status, for backends that talk to the server directly
+/// and can read the `Retry-After` header.
+pub fn classify_status(status: u16, retry_after: Option<Duration>) -> ErrorClass {
+    match status {
+        429 => ErrorClass::RateLimited { retry_after },
+        401 | 403 => ErrorClass::Auth,
+        408 | 500..=599 => ErrorClass::Transient,
+        _ => ErrorClass::Permanent,
+    }
+}
+
+/// Parses a `Retry-After` header value given in seconds.
+pub fn parse_retry_after_header(value: &str) -> Option<Duration> {
+    value.trim().parse::<f64>().ok().filter(|s| *s >= 0.0).map(Duration::from_secs_f64)
+}
+
+/// async-openai does not expose response headers, so `Retry-After` is lost.
+/// OpenAI repeats the hint in the message ("Please try again in 6.5s",
+/// "... in 120ms", "... in 1m20s"), which is what we read here.
+fn parse_retry_hint(message: &str) -> Option<Duration> {
+    let rest = &message[message.find("try again in ")? + "try again in ".len()..];
+    let token: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.')
+        .collect();
+    // Drop the sentence's own full stop
+    let token = token.trim_end_matches('.');
+
+    let mut total = 0.0;
+    let mut number = String::new();
+    let mut chars = token.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let value: f64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'h' => value * 3600.0,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                value / 1000.0
+            }
+            'm' => value * 60.0,
+            's' => value,
+            _ => return None,
+        };
+    }
+
+    if number.is_empty() && total > 0.0 {
+        Some(Duration::from_secs_f64(total))
+    } else {
+        None
+    }
+}
+
+/// Maps a final (non-retried) API error to the matching `TranscriptionError`.
+pub fn into_transcription_error(err: OpenAIError) -> TranscriptionError {
+    match classify(&err) {
+        ErrorClass::RateLimited { .. } => TranscriptionError::RateLimited(err.to_string()),
+        ErrorClass::Auth => TranscriptionError::AuthError(err.to_string()),
+        ErrorClass::QuotaExceeded => TranscriptionError::QuotaExceeded(err.to_string()),
+        ErrorClass::Transient => match &err {
+            OpenAIError::Reqwest(e) if e.is_timeout() => TranscriptionError::Timeout(err.to_string()),
+            OpenAIError::Reqwest(_) => TranscriptionError::NetworkError(err.to_string()),
+            _ => TranscriptionError::ApiError(err.to_string()),
+        },
+        ErrorClass::Permanent => match &err {
+            OpenAIError::InvalidArgument(_) => TranscriptionError::RequestError(err.to_string()),
+            _ => TranscriptionError::ApiError(err.to_string()),
+        },
+    }
+}
+
+/// Runs `op` until it succeeds, fails with a non-retryable error, or the
+/// policy's attempt/time budget is spent.
+pub async fn with_retry<T, E, F, Fut>(policy: &RetryPolicy, label: &str, mut op: F) -> Result<T, TranscriptionError>
+where
+    E: RetryableError,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+
+        let class = err.class();
+        if !class.is_retryable() {
+            return Err(err.into_error());
+        }
+
+        let backoff = policy.backoff_delay(attempt - 1);
+        let delay = match class {
+            ErrorClass::RateLimited { retry_after: Some(hint) } => hint.max(backoff),
+            _ => backoff,
+        };
+
+        if attempt >= policy.max_attempts || started.elapsed() + delay > policy.max_elapsed {
+            return Err(TranscriptionError::RetriesExhausted {
+                attempts: attempt,
+                last_error: err.to_string(),
+            });
+        }
+
+        warn!("{} failed (attempt {}/{}), retrying in {:?}: {}", label, attempt, policy.max_attempts, delay, err);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::error::ApiError;
+
+    fn api_error(message: &str, r#type: Option<&str>, code: Option<&str>) -> OpenAIError {
+        OpenAIError::ApiError(ApiError {
+            message: message.to_string(),
+            r#type: r#type.map(str::to_string),
+            param: None,
+            code: code.map(str::to_string),
+        })
+    }
+
+    fn instant_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            max_elapsed: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn classifies_api_errors() {
+        let limited = api_error("Rate limit reached. Please try again in 1.5s.", Some("requests"), Some("rate_limit_exceeded"));
+        assert_eq!(classify(&limited), ErrorClass::RateLimited { retry_after: Some(Duration::from_millis(1500)) });
+
+        let quota = api_error("You exceeded your current quota", Some("insufficient_quota"), Some("insufficient_quota"));
+        assert_eq!(classify(&quota), ErrorClass::QuotaExceeded);
+
+        let auth = api_error("Incorrect API key provided", Some("invalid_request_error"), Some("invalid_api_key"));
+        assert_eq!(classify(&auth), ErrorClass::Auth);
+
+        let server = api_error("The server had an error", Some("server_error"), None);
+        assert_eq!(classify(&server), ErrorClass::Transient);
+
+        // What async-openai makes of any 5xx response
+        let bad_gateway = api_error("<html><body>502 Bad Gateway</body></html>", None, None);
+        assert_eq!(classify(&bad_gateway), ErrorClass::Transient);
+
+        let compat_limited = api_error("Rate limit exceeded, try again in 2s", Some("invalid_request_error"), None);
+        assert_eq!(classify(&compat_limited), ErrorClass::RateLimited { retry_after: Some(Duration::from_secs(2)) });
+
+        let plain_text = serde_json::from_str::<serde_json::Value>("Too Many Requests").unwrap_err();
+        let plain_text = OpenAIError::JSONDeserialize(plain_text, "429 Too Many Requests".to_string());
+        assert_eq!(classify(&plain_text), ErrorClass::RateLimited { retry_after: None });
+
+        let bad_request = api_error("Invalid file format", Some("invalid_request_error"), None);
+        assert_eq!(classify(&bad_request), ErrorClass::Permanent);
+
+        // Compatible servers send 4xx bodies without type or code too
+        let not_found = api_error("model not found", None, None);
+        assert_eq!(classify(&not_found), ErrorClass::Permanent);
+
+        let empty_body = api_error("", None, None);
+        assert_eq!(classify(&empty_body), ErrorClass::Transient);
+
+        let gateway = api_error("upstream connect error or disconnect/reset before headers", None, None);
+        assert_eq!(classify(&gateway), ErrorClass::Transient);
+    }
+
+    #[test]
+    fn classifies_status_codes() {
+        let hint = Some(Duration::from_secs(3));
+        assert_eq!(classify_status(429, hint), ErrorClass::RateLimited { retry_after: hint });
+        assert_eq!(classify_status(503, None), ErrorClass::Transient);
+        assert_eq!(classify_status(401, None), ErrorClass::Auth);
+        assert_eq!(classify_status(400, None), ErrorClass::Permanent);
+    }
+
+    #[test]
+    fn jitter_varies_between_calls() {
+        let values: Vec<f64> = (0..64).map(|_| jitter()).collect();
+        assert!(values.iter().all(|v| (0.0..1.0).contains(v)));
+        assert!(values.windows(2).any(|w| w[0] != w[1]));
+        assert!(values.iter().any(|v| *v > 0.0));
+    }
+
+    #[test]
+    fn parses_retry_hints() {
+        assert_eq!(parse_retry_hint("Please try again in 20s."), Some(Duration::from_secs(20)));
+        assert_eq!(parse_retry_hint("Please try again in 120ms."), Some(Duration::from_millis(120)));
+        assert_eq!(parse_retry_hint("Please try again in 1m30s."), Some(Duration::from_secs(90)));
+        assert_eq!(parse_retry_hint("Rate limit reached"), None);
+        assert_eq!(parse_retry_after_header(" 7 "), Some(Duration::from_secs(7)));
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_until_success() {
+        let mut calls = 0;
+        let result = with_retry(&instant_policy(3), "test", || {
+            calls += 1;
+            let outcome = if calls < 3 {
+                Err(api_error("The server had an error", Some("server_error"), None))
+            } else {
+                Ok(calls)
+            };
+            async move { outcome }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn stops_on_permanent_errors_and_exhausted_budget() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_retry(&instant_policy(5), "test", || {
+            calls += 1;
+            async { Err(api_error("Incorrect API key provided", None, Some("invalid_api_key"))) }
+        })
+        .await;
+        assert!(matches!(result, Err(TranscriptionError::AuthError(_))));
+        assert_eq!(calls, 1);
+
+        let result: Result<(), _> = with_retry(&instant_policy(2), "test", || async {
+            Err(api_error("Service Unavailable", None, None))
+        })
+        .await;
+        assert!(matches!(result, Err(TranscriptionError::RetriesExhausted { attempts: 2, .. })));
+    }
+}
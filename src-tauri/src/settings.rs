@@ -0,0 +1,237 @@
+use async_openai::config::OpenAIConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::insights::EngineCache;
+use crate::jobs::JobQueue;
+use crate::watcher::LibraryWatcher;
+
+// Same store file the frontend uses for lastFolder/editorState
+const STORE_FILE: &str = "config.json";
+const SETTINGS_KEY: &str = "settings";
+
+pub const DEFAULT_TRANSCRIPTION_MODEL: &str = "whisper-1";
+pub const DEFAULT_CHAT_MODEL: &str = "gpt-4o-mini";
+pub const DEFAULT_CHAT_CONTEXT_TOKENS: usize = 16_385;
+pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+pub const DEFAULT_OLLAMA_MODEL: &str = "llama3.1";
+pub const DEFAULT_OLLAMA_CONTEXT_TOKENS: usize = 8_192;
+pub const DEFAULT_JOB_CONCURRENCY: usize = 2;
+pub const DEFAULT_MAX_RETRIES: u32 = 4;
+pub const DEFAULT_RETRY_BUDGET_SECONDS: u64 = 120;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptionProviderKind {
+    #[default]
+    OpenAi,
+    Local,
+}
+
+/// How recordings' audio is kept in managed storage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioStorageMode {
+    /// Copy the audio next to its transcript (a reflink where supported).
+    #[default]
+    Copy,
+    /// Keep only a link or pointer to the library file.
+    Reference,
+}
+
+/// Where summaries, actions and topics are generated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatProviderKind {
+    #[default]
+    OpenAi,
+    /// A local Ollama server.
+    Ollama,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    pub transcription_provider: TranscriptionProviderKind,
+    /// Path to a GGML/GGUF Whisper model, used by the local provider.
+    pub whisper_model_path: Option<String>,
+    /// ISO 639-1 code forced on the local provider; `None` auto-detects.
+    pub whisper_language: Option<String>,
+    /// Base URL of an OpenAI-compatible API (vLLM, LocalAI, Ollama...);
+    /// `None` uses api.openai.com.
+    pub openai_base_url: Option<String>,
+    /// API key; `None` falls back to the `OPENAI_API_KEY` environment variable.
+    pub openai_api_key: Option<String>,
+    pub transcription_model: String,
+    pub chat_model: String,
+    /// Context window of `chat_model` in tokens; longer transcripts are
+    /// summarized in chunks.
+    pub chat_context_tokens: usize,
+    pub chat_provider: ChatProviderKind,
+    /// Per-library override of `chat_provider`, keyed by library folder.
+    pub library_chat_providers: HashMap<String, ChatProviderKind>,
+    pub ollama_base_url: String,
+    pub ollama_model: String,
+    /// Context window requested from Ollama (`num_ctx`); also sets the
+    /// chunk size for long transcripts.
+    pub ollama_context_tokens: usize,
+    /// Library folders watched for new, renamed and deleted recordings.
+    pub watched_folders: Vec<String>,
+    /// Queue transcription for recordings that appear in a watched folder.
+    pub auto_transcribe: bool,
+    /// Number of background jobs that may run at the same time.
+    pub job_concurrency: usize,
+    /// Folder holding the `open-recorder` storage directory; `None` uses the
+    /// local data dir. Only changed by `move_storage_root`, which moves the data.
+    pub storage_root: Option<String>,
+    pub audio_storage: AudioStorageMode,
+    /// Retries after the first attempt for rate-limited or transient API errors.
+    pub max_retries: u32,
+    /// Total time one API call may spend retrying.
+    pub retry_budget_seconds: u64,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            transcription_provider: TranscriptionProviderKind::default(),
+            whisper_model_path: None,
+            whisper_language: None,
+            openai_base_url: None,
+            openai_api_key: None,
+            transcription_model: DEFAULT_TRANSCRIPTION_MODEL.to_string(),
+            chat_model: DEFAULT_CHAT_MODEL.to_string(),
+            chat_context_tokens: DEFAULT_CHAT_CONTEXT_TOKENS,
+            chat_provider: ChatProviderKind::default(),
+            library_chat_providers: HashMap::new(),
+            ollama_base_url: DEFAULT_OLLAMA_BASE_URL.to_string(),
+            ollama_model: DEFAULT_OLLAMA_MODEL.to_string(),
+            ollama_context_tokens: DEFAULT_OLLAMA_CONTEXT_TOKENS,
+            watched_folders: Vec::new(),
+            auto_transcribe: false,
+            job_concurrency: DEFAULT_JOB_CONCURRENCY,
+            storage_root: None,
+            audio_storage: AudioStorageMode::default(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_budget_seconds: DEFAULT_RETRY_BUDGET_SECONDS,
+        }
+    }
+}
+
+impl AppSettings {
+    pub fn api_key(&self) -> Option<String> {
+        self.openai_api_key
+            .clone()
+            .filter(|key| !key.trim().is_empty())
+            .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+    }
+
+    /// Client configuration for the OpenAI-compatible endpoint.
+    ///
+    /// Returns `None` when talking to api.openai.com without a key. Self-hosted
+    /// servers usually don't check the key, so a custom base URL is enough.
+    pub fn openai_config(&self) -> Option<OpenAIConfig> {
+        let base_url = self.openai_base_url.as_ref().filter(|url| !url.trim().is_empty());
+        let api_key = self.api_key();
+
+        if base_url.is_none() && api_key.is_none() {
+            return None;
+        }
+
+        let mut config = OpenAIConfig::new().with_api_key(api_key.unwrap_or_default());
+        if let Some(url) = base_url {
+            config = config.with_api_base(url.trim_end_matches('/'));
+        }
+        Some(config)
+    }
+
+    /// Chat provider for a recording: the override of the innermost library
+    /// folder containing it, else `chat_provider`.
+    pub fn chat_provider_for(&self, path: Option<&Path>) -> ChatProviderKind {
+        let Some(path) = path else {
+            return self.chat_provider;
+        };
+        self.library_chat_providers
+            .iter()
+            .filter(|(library, _)| path.starts_with(library))
+            .max_by_key(|(library, _)| Path::new(library).components().count())
+            .map(|(_, kind)| *kind)
+            .unwrap_or(self.chat_provider)
+    }
+}
+
+pub fn load_settings(app: &AppHandle) -> Result<AppSettings, String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+
+    match store.get(SETTINGS_KEY) {
+        Some(value) => serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e)),
+        None => Ok(AppSettings::default()),
+    }
+}
+
+pub fn save_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    store.set(SETTINGS_KEY, value);
+    store.save().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, mut settings: AppSettings) -> Result<AppSettings, String> {
+    // Switching the storage root without moving the data would orphan it
+    settings.storage_root = load_settings(&app)?.storage_root;
+    save_settings(&app, &settings)?;
+    apply_settings(&app, &settings);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn reset_settings(app: AppHandle) -> Result<AppSettings, String> {
+    let settings = AppSettings {
+        storage_root: load_settings(&app)?.storage_root,
+        ..AppSettings::default()
+    };
+    save_settings(&app, &settings)?;
+    apply_settings(&app, &settings);
+    Ok(settings)
+}
+
+// Push settings that live in running services
+fn apply_settings(app: &AppHandle, settings: &AppSettings) {
+    if let Some(queue) = app.try_state::<JobQueue>() {
+        queue.set_concurrency(app, settings.job_concurrency);
+    }
+    // Rebuilt with the new client and model on next use
+    if let Some(engine) = app.try_state::<EngineCache>() {
+        engine.invalidate();
+    }
+    if let Some(watcher) = app.try_state::<LibraryWatcher>() {
+        watcher.watch(app, settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn innermost_library_override_wins() {
+        let mut settings = AppSettings::default();
+        settings.library_chat_providers.insert("/recordings".to_string(), ChatProviderKind::Ollama);
+        settings.library_chat_providers.insert("/recordings/work".to_string(), ChatProviderKind::OpenAi);
+
+        let provider = |p: &str| settings.chat_provider_for(Some(Path::new(p)));
+        assert_eq!(provider("/recordings/home/a.mp3"), ChatProviderKind::Ollama);
+        assert_eq!(provider("/recordings/work/b.mp3"), ChatProviderKind::OpenAi);
+        assert_eq!(provider("/elsewhere/c.mp3"), ChatProviderKind::OpenAi);
+        assert_eq!(settings.chat_provider_for(None), ChatProviderKind::OpenAi);
+    }
+}
@@ -0,0 +1,462 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::get_storage_root;
+use super::manifest::{read_manifest, touch_manifest};
+use crate::audio::peaks::PEAKS_FILE;
+use crate::jobs::JobQueue;
+
+/// Checksum manifest at the top of every archive.
+pub const ARCHIVE_MANIFEST: &str = "archive.json";
+
+/// Bump when the archive layout changes.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+// Archived folders of the storage root
+const RECORDINGS_DIR: &str = "audios";
+const AGGREGATE_DIR: &str = "insights";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub version: u32,
+    pub created_at: i64,
+    pub include_audio: bool,
+    pub recordings: usize,
+    pub files: Vec<ArchiveEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    /// Path inside the archive, relative to the storage root.
+    pub path: String,
+    pub size: u64,
+    /// BLAKE3 of the file contents, hex.
+    pub blake3: String,
+    pub modified_at: i64,
+}
+
+/// What `import_library` does with recordings and files that already exist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    /// Keep what is on this machine.
+    #[default]
+    Skip,
+    /// Overwrite with the archived files; local files the archive lacks stay.
+    Replace,
+    /// Replace only when the archived copy was updated more recently.
+    KeepNewer,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportReport {
+    pub path: String,
+    pub recordings: usize,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    /// Recording ids that were new on this machine.
+    pub imported: Vec<String>,
+    pub replaced: Vec<String>,
+    pub skipped: Vec<String>,
+    /// Files of the aggregate `insights/` folder written.
+    pub aggregate_files: usize,
+}
+
+/// Writes managed storage to a zip archive at `destination`: per-recording
+/// manifests, transcripts and insights, the aggregate `insights/` folder and,
+/// with `include_audio`, the audio (taken from the library file for
+/// recordings stored by reference).
+#[tauri::command]
+pub async fn export_library(app: AppHandle, destination: String, include_audio: bool) -> Result<ExportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = get_storage_root(&app)?;
+        export(&root, Path::new(&destination), include_audio)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Restores an archive written by `export_library`. Every file is checked
+/// against the archive's checksums before anything is changed; `conflict`
+/// decides about recordings that already exist.
+#[tauri::command]
+pub async fn import_library(app: AppHandle, archive: String, conflict: ConflictPolicy) -> Result<ImportReport, String> {
+    if app.state::<JobQueue>().has_unfinished() {
+        return Err("Wait for background jobs to finish or cancel them before importing".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = get_storage_root(&app)?;
+        import(&root, Path::new(&archive), conflict)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn export(root: &Path, destination: &Path, include_audio: bool) -> Result<ExportReport, String> {
+    let (files, recordings) = collect_export_files(root, include_audio)?;
+
+    // Written next to the destination and renamed once complete
+    let partial = destination.with_extension("partial");
+    let written = write_archive(&partial, &files, include_audio, recordings);
+    let manifest = match written.and_then(|m| fs::rename(&partial, destination).map(|_| m).map_err(|e| e.to_string())) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+
+    let report = ExportReport {
+        path: destination.to_string_lossy().to_string(),
+        recordings,
+        files: manifest.files.len(),
+        bytes: manifest.files.iter().map(|f| f.size).sum(),
+    };
+    info!("Exported {} recordings ({} files) to {:?}", report.recordings, report.files, destination);
+    Ok(report)
+}
+
+/// Files to archive as (path in archive, file on disk), plus the number of recordings.
+fn collect_export_files(root: &Path, include_audio: bool) -> Result<(Vec<(String, PathBuf)>, usize), String> {
+    let mut files = Vec::new();
+    let mut recordings = 0;
+
+    let audios_dir = root.join(RECORDINGS_DIR);
+    if let Ok(entries) = fs::read_dir(&audios_dir) {
+        for dir in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+            if !dir.is_dir() {
+                continue;
+            }
+            let id = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let mut has_audio = false;
+            for file in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+                let path = file.map_err(|e| e.to_string())?.path();
+                if !path.is_file() {
+                    continue;
+                }
+                let is_audio = path.file_stem().is_some_and(|stem| stem == "audio");
+                // Peaks are rebuilt from the audio when needed
+                if (is_audio && !include_audio) || path.file_name().is_some_and(|name| name == PEAKS_FILE) {
+                    continue;
+                }
+                has_audio |= is_audio;
+                let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                files.push((format!("{}/{}/{}", RECORDINGS_DIR, id, name), path));
+            }
+
+            // Stored by reference: archive the library file as the audio
+            if include_audio && !has_audio {
+                let source = read_manifest(&dir).map(|m| PathBuf::from(m.source_path));
+                if let Some(source) = source.filter(|s| s.is_file()) {
+                    let ext = source.extension().and_then(|e| e.to_str()).unwrap_or("bin");
+                    files.push((format!("{}/{}/audio.{}", RECORDINGS_DIR, id, ext), source));
+                }
+            }
+            recordings += 1;
+        }
+    }
+
+    let aggregate_dir = root.join(AGGREGATE_DIR);
+    if aggregate_dir.is_dir() {
+        collect_tree(&aggregate_dir, AGGREGATE_DIR, &mut files)?;
+    }
+    Ok((files, recordings))
+}
+
+fn collect_tree(dir: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let name = format!("{}/{}", prefix, path.file_name().unwrap_or_default().to_string_lossy());
+        if path.is_dir() {
+            collect_tree(&path, &name, files)?;
+        } else if path.is_file() {
+            files.push((name, path));
+        }
+    }
+    Ok(())
+}
+
+fn write_archive(
+    path: &Path,
+    files: &[(String, PathBuf)],
+    include_audio: bool,
+    recordings: usize,
+) -> Result<ArchiveManifest, String> {
+    let out = fs::File::create(path).map_err(|e| format!("Can't create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(out);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // Audio is compressed already
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let mut entries = Vec::with_capacity(files.len());
+    for (name, source) in files {
+        let is_audio = Path::new(name).file_stem().is_some_and(|stem| stem == "audio");
+        zip.start_file(name.as_str(), if is_audio { stored } else { deflated })
+            .map_err(|e| e.to_string())?;
+        let mut file = fs::File::open(source).map_err(|e| format!("Can't read {}: {}", source.display(), e))?;
+        let (size, hash) = copy_hashed(&mut file, &mut zip)?;
+        entries.push(ArchiveEntry {
+            path: name.clone(),
+            size,
+            blake3: hash,
+            modified_at: modified_unix(source),
+        });
+    }
+
+    let manifest = ArchiveManifest {
+        version: ARCHIVE_FORMAT_VERSION,
+        created_at: crate::now_unix(),
+        include_audio,
+        recordings,
+        files: entries,
+    };
+    zip.start_file(ARCHIVE_MANIFEST, deflated).map_err(|e| e.to_string())?;
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.write_all(&json).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(manifest)
+}
+
+/// Copies `reader` to `writer`, returning the byte count and BLAKE3 hex.
+fn copy_hashed(reader: &mut impl Read, writer: &mut impl Write) -> Result<(u64, String), String> {
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = reader.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+        size += n as u64;
+    }
+    Ok((size, hasher.finalize().to_hex().to_string()))
+}
+
+fn modified_unix(path: &Path) -> i64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Archive paths must stay inside one of the archived folders.
+fn is_safe_entry(path: &str) -> bool {
+    let path = Path::new(path);
+    let first = path.components().next();
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+        && matches!(first, Some(Component::Normal(dir)) if dir == RECORDINGS_DIR || dir == AGGREGATE_DIR)
+}
+
+fn import(root: &Path, archive_path: &Path, conflict: ConflictPolicy) -> Result<ImportReport, String> {
+    let file = fs::File::open(archive_path).map_err(|e| format!("Can't open {}: {}", archive_path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Not a library archive: {}", e))?;
+
+    let manifest: ArchiveManifest = {
+        let mut entry = archive
+            .by_name(ARCHIVE_MANIFEST)
+            .map_err(|_| format!("Not a library archive: {} is missing", ARCHIVE_MANIFEST))?;
+        let mut json = String::new();
+        entry.read_to_string(&mut json).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid {}: {}", ARCHIVE_MANIFEST, e))?
+    };
+    if manifest.version > ARCHIVE_FORMAT_VERSION {
+        return Err(format!("Archive version {} is newer than supported", manifest.version));
+    }
+
+    // Everything is extracted and verified before the storage root is touched
+    let staging = root.join(".import");
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| e.to_string())?;
+    }
+    let extracted = extract_verified(&mut archive, &manifest, &staging);
+    let report = extracted.and_then(|_| apply_import(root, &staging, &manifest, conflict));
+    let _ = fs::remove_dir_all(&staging);
+
+    let report = report?;
+    info!(
+        "Imported {:?}: {} new, {} replaced, {} skipped",
+        archive_path,
+        report.imported.len(),
+        report.replaced.len(),
+        report.skipped.len()
+    );
+    Ok(report)
+}
+
+fn extract_verified<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    manifest: &ArchiveManifest,
+    staging: &Path,
+) -> Result<(), String> {
+    for entry in &manifest.files {
+        if !is_safe_entry(&entry.path) {
+            return Err(format!("Refusing archive entry {}", entry.path));
+        }
+        let target = staging.join(&entry.path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut file = archive
+            .by_name(&entry.path)
+            .map_err(|_| format!("Archive is missing {}", entry.path))?;
+        let mut out = fs::File::create(&target).map_err(|e| e.to_string())?;
+        let (size, hash) = copy_hashed(&mut file, &mut out)?;
+        if size != entry.size || hash != entry.blake3 {
+            return Err(format!("Checksum mismatch for {}, the archive is damaged", entry.path));
+        }
+    }
+    Ok(())
+}
+
+fn apply_import(root: &Path, staging: &Path, manifest: &ArchiveManifest, conflict: ConflictPolicy) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+
+    let audios_dir = root.join(RECORDINGS_DIR);
+    fs::create_dir_all(&audios_dir).map_err(|e| e.to_string())?;
+    if let Ok(entries) = fs::read_dir(staging.join(RECORDINGS_DIR)) {
+        for staged in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+            let id = staged.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let target = audios_dir.join(&id);
+            if !target.exists() {
+                fs::rename(&staged, &target).map_err(|e| e.to_string())?;
+                report.imported.push(id);
+                continue;
+            }
+
+            let replace = match conflict {
+                ConflictPolicy::Skip => false,
+                ConflictPolicy::Replace => true,
+                ConflictPolicy::KeepNewer => {
+                    let archived = read_manifest(&staged).map(|m| m.updated_at).unwrap_or(0);
+                    let local = read_manifest(&target).map(|m| m.updated_at).unwrap_or(0);
+                    archived > local
+                }
+            };
+            if !replace {
+                report.skipped.push(id);
+                continue;
+            }
+            for file in fs::read_dir(&staged).map_err(|e| e.to_string())? {
+                let from = file.map_err(|e| e.to_string())?.path();
+                let to = target.join(from.file_name().unwrap_or_default());
+                fs::rename(&from, &to).map_err(|e| e.to_string())?;
+            }
+            touch_manifest(&target);
+            report.replaced.push(id);
+        }
+    }
+
+    // Aggregate insights are merged file by file
+    let prefix = format!("{}/", AGGREGATE_DIR);
+    for entry in manifest.files.iter().filter(|f| f.path.starts_with(&prefix)) {
+        let target = root.join(&entry.path);
+        let write = !target.exists()
+            || match conflict {
+                ConflictPolicy::Skip => false,
+                ConflictPolicy::Replace => true,
+                ConflictPolicy::KeepNewer => entry.modified_at > modified_unix(&target),
+            };
+        if !write {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        match fs::rename(staging.join(&entry.path), &target) {
+            Ok(()) => report.aggregate_files += 1,
+            Err(e) => warn!("Failed to import {}: {}", entry.path, e),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    #[test]
+    fn round_trips_without_audio_and_skips_existing() {
+        let base = scratch_dir("archive-round-trip");
+        let source = base.join("source");
+        let recording = source.join(RECORDINGS_DIR).join("abc");
+        fs::create_dir_all(&recording).unwrap();
+        fs::write(recording.join("audio.mp3"), b"audio").unwrap();
+        fs::write(recording.join("transcript.txt"), "hello").unwrap();
+        fs::create_dir_all(source.join(AGGREGATE_DIR)).unwrap();
+        fs::write(source.join(AGGREGATE_DIR).join("topics.json"), "[]").unwrap();
+
+        let archive = base.join("library.zip");
+        let exported = export(&source, &archive, false).unwrap();
+        assert_eq!((exported.recordings, exported.files), (1, 2));
+
+        let target = base.join("target");
+        fs::create_dir_all(target.join(RECORDINGS_DIR).join("def")).unwrap();
+        let report = import(&target, &archive, ConflictPolicy::Skip).unwrap();
+        assert_eq!(report.imported, vec!["abc".to_string()]);
+        assert_eq!(report.aggregate_files, 1);
+        let imported = target.join(RECORDINGS_DIR).join("abc");
+        assert_eq!(fs::read_to_string(imported.join("transcript.txt")).unwrap(), "hello");
+        assert!(!imported.join("audio.mp3").exists());
+
+        let again = import(&target, &archive, ConflictPolicy::Skip).unwrap();
+        assert_eq!(again.skipped, vec!["abc".to_string()]);
+    }
+
+    #[test]
+    fn rejects_archives_with_bad_checksums() {
+        let base = scratch_dir("archive-checksum");
+        let archive = base.join("damaged.zip");
+        let manifest = ArchiveManifest {
+            version: ARCHIVE_FORMAT_VERSION,
+            created_at: 0,
+            include_audio: false,
+            recordings: 1,
+            files: vec![ArchiveEntry {
+                path: "audios/abc/transcript.txt".to_string(),
+                size: 5,
+                blake3: "0".repeat(64),
+                modified_at: 0,
+            }],
+        };
+        let mut zip = ZipWriter::new(fs::File::create(&archive).unwrap());
+        zip.start_file("audios/abc/transcript.txt", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"hello").unwrap();
+        zip.start_file(ARCHIVE_MANIFEST, SimpleFileOptions::default()).unwrap();
+        zip.write_all(&serde_json::to_vec(&manifest).unwrap()).unwrap();
+        zip.finish().unwrap();
+
+        let target = base.join("target");
+        fs::create_dir_all(&target).unwrap();
+        assert!(import(&target, &archive, ConflictPolicy::Replace).is_err());
+        assert!(!target.join(RECORDINGS_DIR).join("abc").exists());
+    }
+
+    #[test]
+    fn only_accepts_entries_inside_archived_folders() {
+        assert!(is_safe_entry("audios/abc/transcript.txt"));
+        assert!(is_safe_entry("insights/topics.json"));
+        assert!(!is_safe_entry("audios/../../etc/passwd"));
+        assert!(!is_safe_entry("/audios/abc"));
+        assert!(!is_safe_entry("library.db"));
+    }
+}
@@ -0,0 +1,215 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::manifest::{update_manifest, InsightsInfo};
+use super::{get_cache_dir, get_storage_root};
+use crate::commands::transcription::{self, TranscriptInsights};
+
+/// Full insights record in a managed directory; `summary.txt` and
+/// `actions.json` are plain copies of its fields for other tools.
+pub const INSIGHTS_FILE: &str = "insights.json";
+pub const SUMMARY_FILE: &str = "summary.txt";
+pub const ACTIONS_FILE: &str = "actions.json";
+
+/// Bump when the layout of `insights.json` changes.
+pub const INSIGHTS_FORMAT_VERSION: u32 = 1;
+
+// Written once existing cache entries were copied into managed directories
+const LINKED_MARKER: &str = ".linked";
+
+/// Contents of `insights.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredInsights {
+    pub version: u32,
+    /// `transcript_hash` of the transcript the insights were generated from;
+    /// they are ignored once the transcript changes.
+    pub transcript_hash: String,
+    pub generated_at: i64,
+    /// Chat model that generated them, unknown for insights taken from the cache.
+    pub model: Option<String>,
+    #[serde(flatten)]
+    pub insights: TranscriptInsights,
+}
+
+/// Key linking insights to a transcript version: MD5 of the trimmed text.
+/// Also the file name in the global cache.
+pub fn transcript_hash(text: &str) -> String {
+    format!("{:x}", md5::compute(text.trim()))
+}
+
+/// Insights stored in `dir` for the transcript with `hash`, `None` if there
+/// are none or they belong to another version of the transcript.
+pub fn read_recording_insights(dir: &Path, hash: &str) -> Option<TranscriptInsights> {
+    let path = dir.join(INSIGHTS_FILE);
+    let content = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str::<StoredInsights>(&content) {
+        Ok(stored) if stored.version > INSIGHTS_FORMAT_VERSION => {
+            warn!("Ignoring {:?}: version {} is newer than supported", path, stored.version);
+            None
+        }
+        Ok(stored) if stored.transcript_hash != hash => {
+            info!("Insights in {:?} are for an older transcript", dir);
+            None
+        }
+        Ok(stored) => Some(stored.insights),
+        Err(e) => {
+            warn!("Failed to parse {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Writes `insights.json` plus `summary.txt`/`actions.json` for the fields
+/// present, and records them in the manifest.
+pub fn write_recording_insights(dir: &Path, stored: &StoredInsights) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(stored).map_err(|e| e.to_string())?;
+    fs::write(dir.join(INSIGHTS_FILE), json).map_err(|e| e.to_string())?;
+    if let Some(summary) = &stored.insights.summary {
+        fs::write(dir.join(SUMMARY_FILE), summary).map_err(|e| e.to_string())?;
+    }
+    if let Some(actions) = &stored.insights.actions {
+        let json = serde_json::to_string_pretty(actions).map_err(|e| e.to_string())?;
+        fs::write(dir.join(ACTIONS_FILE), json).map_err(|e| e.to_string())?;
+    }
+
+    update_manifest(dir, |m| {
+        m.insights = Some(InsightsInfo {
+            transcript_hash: stored.transcript_hash.clone(),
+            generated_at: stored.generated_at,
+            model: stored.model.clone(),
+        })
+    })?;
+    Ok(())
+}
+
+/// Global insights cache keyed by `transcript_hash`. Only used to avoid
+/// regenerating insights for identical transcripts; managed directories hold
+/// the insights of their recording.
+pub fn get_insights_cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(get_cache_dir(app)?.join("summaries"))
+}
+
+pub fn read_cached_insights(cache_dir: &Path, hash: &str) -> Option<TranscriptInsights> {
+    let cache_file = cache_dir.join(format!("{}.txt", hash));
+    let content = fs::read_to_string(&cache_file).ok()?;
+    match serde_json::from_str::<TranscriptInsights>(&content) {
+        Ok(insights) => Some(insights),
+        // Old format: plain text summary
+        Err(_) => Some(TranscriptInsights {
+            summary: Some(content),
+            actions: None,
+            topics: None,
+        }),
+    }
+}
+
+pub fn write_cached_insights(cache_dir: &Path, hash: &str, insights: &TranscriptInsights) -> Result<(), String> {
+    fs::create_dir_all(cache_dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(insights).map_err(|e| e.to_string())?;
+    fs::write(cache_dir.join(format!("{}.txt", hash)), json).map_err(|e| e.to_string())
+}
+
+/// Copies insights from the global cache into the managed directories of
+/// the recordings they belong to. Runs once, older versions only kept them
+/// in the cache.
+pub fn migrate_cached_insights(app: &AppHandle) {
+    let (Ok(cache_dir), Ok(root)) = (get_insights_cache_dir(app), get_storage_root(app)) else {
+        return;
+    };
+    if !cache_dir.is_dir() || cache_dir.join(LINKED_MARKER).exists() {
+        return;
+    }
+    let linked = link_cached_insights(&root.join("audios"), &cache_dir);
+    info!("Linked {} cached insights to their recordings", linked);
+    if let Err(e) = fs::write(cache_dir.join(LINKED_MARKER), "") {
+        warn!("Failed to mark insights cache as migrated: {}", e);
+    }
+}
+
+fn link_cached_insights(audios_dir: &Path, cache_dir: &Path) -> usize {
+    let Ok(entries) = fs::read_dir(audios_dir) else {
+        return 0;
+    };
+    let mut linked = 0;
+    for dir in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+        if !dir.is_dir() || dir.join(INSIGHTS_FILE).exists() {
+            continue;
+        }
+        let text = match transcription::read_transcript_json(&dir) {
+            Some(stored) => stored.transcript.text,
+            None => match fs::read_to_string(dir.join("transcript.txt")) {
+                Ok(text) => text,
+                Err(_) => continue,
+            },
+        };
+        let hash = transcript_hash(&text);
+        let Some(insights) = read_cached_insights(cache_dir, &hash) else {
+            continue;
+        };
+        let stored = StoredInsights {
+            version: INSIGHTS_FORMAT_VERSION,
+            transcript_hash: hash,
+            generated_at: crate::now_unix(),
+            model: None,
+            insights,
+        };
+        match write_recording_insights(&dir, &stored) {
+            Ok(()) => linked += 1,
+            Err(e) => warn!("Failed to write insights into {:?}: {}", dir, e),
+        }
+    }
+    linked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    fn summary_only(summary: &str) -> TranscriptInsights {
+        TranscriptInsights {
+            summary: Some(summary.to_string()),
+            actions: None,
+            topics: None,
+        }
+    }
+
+    #[test]
+    fn recording_insights_follow_the_transcript_version() {
+        let dir = scratch_dir("insights-version");
+        let stored = StoredInsights {
+            version: INSIGHTS_FORMAT_VERSION,
+            transcript_hash: transcript_hash("first take"),
+            generated_at: 0,
+            model: Some("llama3.1".to_string()),
+            insights: summary_only("A summary"),
+        };
+        write_recording_insights(&dir, &stored).unwrap();
+
+        assert_eq!(fs::read_to_string(dir.join(SUMMARY_FILE)).unwrap(), "A summary");
+        assert!(read_recording_insights(&dir, &transcript_hash("  first take\n")).is_some());
+        assert!(read_recording_insights(&dir, &transcript_hash("edited take")).is_none());
+    }
+
+    #[test]
+    fn links_cached_insights_to_recordings() {
+        let base = scratch_dir("insights-link");
+        let audios = base.join("audios");
+        let cache = base.join("summaries");
+        let recording = audios.join("abc");
+        fs::create_dir_all(&recording).unwrap();
+        fs::write(recording.join("transcript.txt"), "hello there").unwrap();
+        fs::create_dir_all(&cache).unwrap();
+        // Old plain-text cache entry
+        fs::write(cache.join(format!("{}.txt", transcript_hash("hello there"))), "Greeting").unwrap();
+
+        assert_eq!(link_cached_insights(&audios, &cache), 1);
+        let insights = read_recording_insights(&recording, &transcript_hash("hello there")).unwrap();
+        assert_eq!(insights.summary.as_deref(), Some("Greeting"));
+        assert_eq!(link_cached_insights(&audios, &cache), 0);
+    }
+}
@@ -0,0 +1,224 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::audio::recorded_at::RecordedAt;
+use crate::audio::{AudioFormat, AudioTags};
+
+/// File in each managed directory describing the recording and its artifacts.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Bump when the layout of `manifest.json` changes.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Contents of `manifest.json`. Written when the managed directory is
+/// created and updated whenever an artifact in it is written.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RecordingManifest {
+    pub version: u32,
+    pub recording_id: String,
+    pub content_hash: String,
+    /// Library file the recording was last seen at.
+    pub source_path: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub probe: Option<ProbeInfo>,
+    /// How the audio is kept in the directory, `None` before it was needed.
+    pub audio: Option<AudioLink>,
+    pub transcription: Option<TranscriptionInfo>,
+    pub insights: Option<InsightsInfo>,
+    /// Files in the managed directory, refreshed on every write.
+    pub artifacts: Vec<Artifact>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeInfo {
+    /// `audio::PROBE_VERSION` that produced the fields below.
+    #[serde(default)]
+    pub probe_version: i32,
+    pub ext: String,
+    pub size: u64,
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub duration_estimated: bool,
+    #[serde(default)]
+    pub format: AudioFormat,
+    #[serde(default)]
+    pub tags: AudioTags,
+    #[serde(default)]
+    pub recorded_at: Option<RecordedAt>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioLink {
+    Copy,
+    /// Copy-on-write clone; shares disk blocks with the library file.
+    Reflink,
+    Hardlink,
+    /// No audio, only `source.path` pointing at the library file.
+    Reference,
+}
+
+impl AudioLink {
+    /// True if the audio takes no disk space beyond the library file.
+    pub fn is_shared(self) -> bool {
+        !matches!(self, AudioLink::Copy)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionInfo {
+    pub provider: String,
+    pub model: String,
+    pub language: String,
+    pub transcribed_at: i64,
+    /// Set when the user saved an edited transcript.
+    pub edited_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InsightsInfo {
+    /// Transcript version the insights belong to, see `transcript_hash`.
+    pub transcript_hash: String,
+    pub generated_at: i64,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub name: String,
+    pub size: u64,
+    pub modified_at: i64,
+}
+
+pub fn read_manifest(dir: &Path) -> Option<RecordingManifest> {
+    load_manifest(dir).unwrap_or_else(|e| {
+        warn!("Ignoring {:?}: {}", dir.join(MANIFEST_FILE), e);
+        None
+    })
+}
+
+/// `Ok(None)` if `dir` has no manifest yet; an error if it has one that can't
+/// be read, parsed or is from a newer version.
+fn load_manifest(dir: &Path) -> Result<Option<RecordingManifest>, String> {
+    let path = dir.join(MANIFEST_FILE);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+    let manifest = serde_json::from_str::<RecordingManifest>(&content).map_err(|e| e.to_string())?;
+    if manifest.version > MANIFEST_VERSION {
+        return Err(format!("version {} is newer than supported", manifest.version));
+    }
+    Ok(Some(manifest))
+}
+
+/// Applies `update` to the manifest in `dir` (a fresh one if there is none),
+/// refreshes the artifact list and writes it back. A manifest that exists but
+/// can't be read is left alone rather than replaced.
+pub fn update_manifest(dir: &Path, update: impl FnOnce(&mut RecordingManifest)) -> Result<RecordingManifest, String> {
+    let now = crate::now_unix();
+    let mut manifest = load_manifest(dir)?.unwrap_or_else(|| RecordingManifest {
+        created_at: now,
+        ..Default::default()
+    });
+    update(&mut manifest);
+    manifest.version = MANIFEST_VERSION;
+    manifest.updated_at = now;
+    manifest.artifacts = list_artifacts(dir)?;
+
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    // Write then rename so readers never see a half-written manifest
+    let tmp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+    fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, dir.join(MANIFEST_FILE)).map_err(|e| e.to_string())?;
+    Ok(manifest)
+}
+
+/// Refreshes only the artifact list, for writers that don't change anything else.
+pub fn touch_manifest(dir: &Path) {
+    if let Err(e) = update_manifest(dir, |_| {}) {
+        warn!("Failed to update manifest in {:?}: {}", dir, e);
+    }
+}
+
+fn list_artifacts(dir: &Path) -> Result<Vec<Artifact>, String> {
+    let mut artifacts = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(MANIFEST_FILE) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified_at = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        artifacts.push(Artifact {
+            name,
+            size: metadata.len(),
+            modified_at,
+        });
+    }
+    artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(artifacts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    #[test]
+    fn updates_keep_fields_and_refresh_artifacts() {
+        let dir = scratch_dir("manifest-update");
+        fs::write(dir.join("audio.mp3"), b"abc").unwrap();
+        update_manifest(&dir, |m| m.source_path = "/rec/a.mp3".to_string()).unwrap();
+
+        fs::write(dir.join("transcript.txt"), "hello").unwrap();
+        let manifest = update_manifest(&dir, |_| {}).unwrap();
+
+        assert_eq!(manifest.source_path, "/rec/a.mp3");
+        let names: Vec<&str> = manifest.artifacts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["audio.mp3", "transcript.txt"]);
+        assert_eq!(read_manifest(&dir).unwrap().artifacts.len(), 2);
+    }
+
+    #[test]
+    fn ignores_newer_manifest_versions() {
+        let dir = scratch_dir("manifest-version");
+        fs::write(dir.join(MANIFEST_FILE), format!("{{\"version\": {}}}", MANIFEST_VERSION + 1)).unwrap();
+
+        assert!(read_manifest(&dir).is_none());
+    }
+
+    #[test]
+    fn leaves_unreadable_manifests_alone() {
+        let dir = scratch_dir("manifest-unreadable");
+        let newer = format!("{{\"version\": {}}}", MANIFEST_VERSION + 1);
+        fs::write(dir.join(MANIFEST_FILE), &newer).unwrap();
+
+        assert!(update_manifest(&dir, |m| m.source_path = "/rec/a.mp3".to_string()).is_err());
+        assert_eq!(fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap(), newer);
+
+        fs::write(dir.join(MANIFEST_FILE), "{not json").unwrap();
+        assert!(update_manifest(&dir, |_| {}).is_err());
+        assert_eq!(fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap(), "{not json");
+    }
+}
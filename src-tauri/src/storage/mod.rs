@@ -0,0 +1,465 @@
+use log::{info, warn};
+use md5;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::audio::{self, formats};
+use crate::library::LibraryIndex;
+use crate::settings::{self, AudioStorageMode};
+
+mod archive;
+mod insights;
+mod manifest;
+mod relink;
+mod relocate;
+mod usage;
+
+pub use archive::{export_library, import_library, ConflictPolicy, ExportReport, ImportReport};
+pub use insights::{
+    get_insights_cache_dir, migrate_cached_insights, read_cached_insights, read_recording_insights,
+    transcript_hash, write_cached_insights, write_recording_insights, StoredInsights, INSIGHTS_FORMAT_VERSION,
+};
+pub use manifest::{read_manifest, touch_manifest, update_manifest, AudioLink, RecordingManifest, TranscriptionInfo};
+pub use relink::{relink_managed_storage, RelinkReport};
+pub use relocate::move_storage_root;
+pub use usage::{clean_storage, get_storage_usage, CleanupOptions, CleanupReport, StorageUsage};
+
+/// Folder name appended to the configured storage location.
+pub const ROOT_DIR_NAME: &str = "open-recorder";
+
+/// Storage location used when `storage_root` isn't set.
+pub fn default_storage_parent(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().local_data_dir().map_err(|e| e.to_string())
+}
+
+/// `<storage_root setting>/open-recorder`, by default under the local data
+/// dir. Changed only through `move_storage_root`.
+pub fn get_storage_root(app: &AppHandle) -> Result<PathBuf, String> {
+    let configured = settings::load_settings(app)
+        .ok()
+        .and_then(|s| s.storage_root)
+        .filter(|root| !root.trim().is_empty());
+    let parent = match configured {
+        Some(root) => PathBuf::from(root),
+        None => default_storage_parent(app)?,
+    };
+    Ok(parent.join(ROOT_DIR_NAME))
+}
+
+/// Caches that can be rebuilt (insight results keyed by transcript hash,
+/// map-reduce chunk results).
+pub fn get_cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(get_storage_root(app)?.join("cache"))
+}
+
+/// Moves the insights cache from `<local data dir>/summaries`, where older
+/// versions kept it, under the storage root.
+pub fn migrate_legacy_cache(app: &AppHandle) {
+    let (Ok(data_dir), Ok(cache_dir)) = (app.path().local_data_dir(), get_cache_dir(app)) else {
+        return;
+    };
+    let legacy = data_dir.join("summaries");
+    let target = cache_dir.join("summaries");
+    if !legacy.is_dir() || target.exists() {
+        return;
+    }
+    let moved = fs::create_dir_all(&cache_dir).and_then(|_| fs::rename(&legacy, &target));
+    match moved {
+        Ok(()) => info!("Moved insights cache from {:?} to {:?}", legacy, target),
+        Err(e) => warn!("Failed to move insights cache {:?}: {}", legacy, e),
+    }
+}
+
+pub fn get_insights_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let root = get_storage_root(app)?;
+    Ok(root.join("insights"))
+}
+
+/// Hex characters of the content hash used as a managed directory name.
+pub const RECORDING_ID_LEN: usize = 32;
+
+/// Managed directory name used before content hashes: file stem plus the
+/// first 6 characters of the MD5 of the full source path. Only used to find
+/// and migrate old directories.
+pub fn get_managed_audio_dir_name(source_path: &PathBuf) -> String {
+    let filename = source_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    // We use the full path for the hash to ensure uniqueness even if filenames are same
+    let full_path_str = source_path.to_string_lossy().to_string();
+    let digest = md5::compute(full_path_str);
+    let hash = format!("{:x}", digest);
+    // Take first 6 chars of hash
+    let hash_short = &hash[0..6];
+
+    format!("{}-{}", filename, hash_short)
+}
+
+/// Recording id for a content hash (see `audio::content_hash`).
+pub fn recording_id(content_hash: &str) -> &str {
+    &content_hash[..RECORDING_ID_LEN.min(content_hash.len())]
+}
+
+pub fn is_recording_id(name: &str) -> bool {
+    name.len() == RECORDING_ID_LEN && name.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Content hash of a source file, cached in the library index when available.
+pub fn get_content_hash(app: &AppHandle, source_path: &PathBuf) -> Result<String, String> {
+    match app.try_state::<LibraryIndex>() {
+        Some(index) => index.content_hash(source_path),
+        None => audio::content_hash(source_path),
+    }
+}
+
+/// Managed directory of a source file. Recordings are identified by content,
+/// so moved, renamed or copied files share one directory.
+pub fn get_managed_path(app: &AppHandle, source_path: &PathBuf) -> Result<PathBuf, String> {
+    match get_content_hash(app, source_path) {
+        Ok(hash) => managed_dir_for(app, source_path, &hash),
+        // Deleted before it was ever indexed, so there is no hash to go by
+        Err(e) if !source_path.exists() => find_managed_dir_by_source(app, source_path).ok_or(e),
+        Err(e) => Err(e),
+    }
+}
+
+/// Managed directory of `source_path` found by name: a legacy path-named
+/// directory, or one whose manifest points at the file.
+pub fn find_managed_dir_by_source(app: &AppHandle, source_path: &PathBuf) -> Option<PathBuf> {
+    if let Some(legacy) = legacy_managed_dir(app, source_path) {
+        return Some(legacy);
+    }
+    let audios_dir = get_storage_root(app).ok()?.join("audios");
+    let source = source_path.to_string_lossy();
+    fs::read_dir(&audios_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|dir| read_manifest(dir).is_some_and(|m| m.source_path == source))
+}
+
+/// Path-named directory of `source_path` from before recordings were
+/// identified by content, if there is one.
+pub fn legacy_managed_dir(app: &AppHandle, source_path: &PathBuf) -> Option<PathBuf> {
+    let legacy = get_storage_root(app).ok()?.join("audios").join(get_managed_audio_dir_name(source_path));
+    legacy.is_dir().then_some(legacy)
+}
+
+/// Managed directories keyed by the library file their manifest names; one
+/// pass over storage for `find_managed_dir_by_source` on many files.
+pub fn managed_dirs_by_source(app: &AppHandle) -> HashMap<String, PathBuf> {
+    let Ok(root) = get_storage_root(app) else {
+        return HashMap::new();
+    };
+    let Ok(entries) = fs::read_dir(root.join("audios")) else {
+        return HashMap::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter_map(|dir| read_manifest(&dir).map(|m| (m.source_path, dir)))
+        .collect()
+}
+
+/// Managed directory for `source_path` with content hash `hash`. A legacy
+/// path-named directory for the same source is moved into place on first use.
+pub fn managed_dir_for(app: &AppHandle, source_path: &PathBuf, hash: &str) -> Result<PathBuf, String> {
+    let audios_dir = get_storage_root(app)?.join("audios");
+    let managed_path = audios_dir.join(recording_id(hash));
+
+    if !managed_path.exists() {
+        let legacy = audios_dir.join(get_managed_audio_dir_name(source_path));
+        if legacy.is_dir() {
+            fs::rename(&legacy, &managed_path).map_err(|e| e.to_string())?;
+            info!("Moved managed directory {:?} to {:?}", legacy, managed_path);
+        }
+    }
+
+    Ok(managed_path)
+}
+
+/// True if `dir` holds a transcript (`transcript.json` or `transcript.txt`).
+pub fn dir_has_transcript(dir: &Path) -> bool {
+    dir.join("transcript.json").exists() || dir.join("transcript.txt").exists()
+}
+
+/// True if the managed directory holds a transcript (`transcript.json` or `transcript.txt`).
+pub fn has_managed_transcript(app: &AppHandle, source_path: &PathBuf) -> bool {
+    match get_managed_path(app, source_path) {
+        Ok(dir) => dir_has_transcript(&dir),
+        Err(_) => false,
+    }
+}
+
+/// Pointer to the library file, written in reference mode when the audio
+/// can't be linked into the managed directory. Kept current by
+/// `ensure_audio_dir` and read by `audio_source`.
+pub const SOURCE_POINTER_FILE: &str = "source.path";
+
+/// Creates the managed directory and its manifest without touching the
+/// audio; enough for anything that only writes derived artifacts.
+pub fn ensure_managed_dir(app: &AppHandle, source_path: &PathBuf) -> Result<PathBuf, String> {
+    let hash = get_content_hash(app, source_path)?;
+    let managed_path = managed_dir_for(app, source_path, &hash)?;
+
+    if !managed_path.exists() {
+        fs::create_dir_all(&managed_path).map_err(|e| e.to_string())?;
+    }
+
+    // Only rewritten for new directories, when the recording was moved or
+    // when the probe is from an older `PROBE_VERSION`
+    let source = source_path.to_string_lossy();
+    let stale = !read_manifest(&managed_path).is_some_and(|m| {
+        m.source_path == source && m.probe.is_some_and(|p| p.probe_version >= audio::PROBE_VERSION)
+    });
+    if stale {
+        let probed = audio::probe(source_path).unwrap_or_default();
+        let probe = manifest::ProbeInfo {
+            probe_version: audio::PROBE_VERSION,
+            ext: audio_ext(source_path).to_lowercase(),
+            size: fs::metadata(source_path).map(|m| m.len()).unwrap_or(0),
+            duration: probed.duration.map(|d| d.seconds),
+            duration_estimated: probed.duration.is_some_and(|d| !d.exact),
+            format: probed.format,
+            tags: probed.tags,
+            recorded_at: probed.recorded_at,
+        };
+        let written = update_manifest(&managed_path, |m| {
+            m.recording_id = recording_id(&hash).to_string();
+            m.content_hash = hash.clone();
+            m.source_path = source.to_string();
+            m.probe = Some(probe);
+        });
+        if let Err(e) = written {
+            warn!("Failed to write manifest for {:?}: {}", source_path, e);
+        }
+    }
+
+    Ok(managed_path)
+}
+
+/// `ensure_managed_dir` plus the audio, placed as the `audio_storage`
+/// setting says: a copy (a reflink where the filesystem supports it), or in
+/// reference mode a reflink or hardlink, falling back to a pointer file.
+/// Videos are never copied whole, only their audio track.
+/// Only needed to transcribe; read paths use `get_managed_path`.
+pub fn ensure_audio_dir(app: &AppHandle, source_path: &PathBuf) -> Result<PathBuf, String> {
+    let managed_path = ensure_managed_dir(app, source_path)?;
+    let mode = settings::load_settings(app)
+        .map(|s| s.audio_storage)
+        .unwrap_or_default();
+
+    let dest_file = managed_path.join(format!("audio.{}", audio_ext(source_path)));
+    let pointer = managed_path.join(SOURCE_POINTER_FILE);
+    // Videos fall back to a pointer in copy mode too, see `place_audio`
+    let video = formats::lookup(source_path).is_some_and(|format| format.video);
+    if dest_file.exists() {
+        return Ok(managed_path);
+    }
+    if (mode == AudioStorageMode::Reference || video) && pointer.exists() {
+        // The recording may have moved since the pointer was written
+        let source = source_path.to_string_lossy();
+        if fs::read_to_string(&pointer).map_or(true, |target| target != source) {
+            fs::write(&pointer, source.as_bytes()).map_err(|e| e.to_string())?;
+        }
+        return Ok(managed_path);
+    }
+
+    let link = place_audio(source_path, &dest_file, mode).map_err(|e| e.to_string())?;
+    if link == AudioLink::Reference {
+        fs::write(&pointer, source_path.to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+    }
+    info!("Stored audio for {:?} as {:?}", source_path, link);
+    if let Err(e) = update_manifest(&managed_path, |m| m.audio = Some(link)) {
+        warn!("Failed to update manifest for {:?}: {}", source_path, e);
+    }
+
+    Ok(managed_path)
+}
+
+fn place_audio(source_path: &Path, dest_file: &Path, mode: AudioStorageMode) -> std::io::Result<AudioLink> {
+    let video = formats::lookup(source_path).is_some_and(|format| format.video);
+    match mode {
+        // Copying a whole video would mostly store frames nobody plays here;
+        // keep only its audio track, or share the file when that fails
+        AudioStorageMode::Copy if video => match formats::extract_audio_track(source_path, dest_file) {
+            Ok(()) => Ok(AudioLink::Copy),
+            Err(e) => {
+                warn!("Referencing {:?} instead of copying it: {}", source_path, e);
+                place_audio(source_path, dest_file, AudioStorageMode::Reference)
+            }
+        },
+        AudioStorageMode::Copy => match reflink_copy::reflink_or_copy(source_path, dest_file)? {
+            None => Ok(AudioLink::Reflink),
+            Some(_) => Ok(AudioLink::Copy),
+        },
+        // Both only work within one filesystem
+        AudioStorageMode::Reference => {
+            if reflink_copy::reflink(source_path, dest_file).is_ok() {
+                return Ok(AudioLink::Reflink);
+            }
+            if fs::hard_link(source_path, dest_file).is_ok() {
+                return Ok(AudioLink::Hardlink);
+            }
+            Ok(AudioLink::Reference)
+        }
+    }
+}
+
+fn audio_ext(source_path: &Path) -> &str {
+    source_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin")
+}
+
+/// Audio file to read for a recording: the one in the managed directory if
+/// there is one, else the library file itself, else wherever the pointer
+/// file last saw it.
+pub fn audio_source(managed_dir: &Path, source_path: &Path) -> Result<PathBuf, String> {
+    let managed = managed_dir.join(format!("audio.{}", audio_ext(source_path)));
+    if managed.exists() {
+        return Ok(managed);
+    }
+    if source_path.exists() {
+        return Ok(source_path.to_path_buf());
+    }
+    let pointed = fs::read_to_string(managed_dir.join(SOURCE_POINTER_FILE))
+        .ok()
+        .map(|target| PathBuf::from(target.trim_end()))
+        .filter(|target| target.exists());
+    if let Some(target) = pointed {
+        return Ok(target);
+    }
+    Err(format!("Audio of {} is no longer available", source_path.display()))
+}
+
+#[tauri::command]
+pub async fn get_storage_root_command(app: AppHandle) -> Result<String, String> {
+    let root = get_storage_root(&app)?;
+    Ok(root.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn get_insights_dir_command(app: AppHandle) -> Result<String, String> {
+    let dir = get_insights_dir(&app)?;
+    Ok(dir.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn ensure_audio_dir_command(app: AppHandle, source_path: String) -> Result<String, String> {
+    let path = PathBuf::from(source_path);
+    let managed_path = ensure_audio_dir(&app, &path)?;
+    Ok(managed_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn list_managed_recordings(app: AppHandle) -> Result<Vec<String>, String> {
+    let root = get_storage_root(&app)?;
+    let audios_dir = root.join("audios");
+    
+    if !audios_dir.exists() {
+        return Ok(Vec::new());
+    }
+    
+    let mut recordings = Vec::new();
+    let entries = fs::read_dir(audios_dir).map_err(|e| e.to_string())?;
+    
+    for entry in entries {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            recordings.push(dir_name);
+        }
+    }
+    
+    Ok(recordings)
+}
+
+/// Manifest of a recording's managed directory, `None` if the recording was
+/// never imported. Directories created before manifests existed get one on
+/// first request.
+#[tauri::command]
+pub async fn get_recording_manifest(app: AppHandle, source_path: String) -> Result<Option<RecordingManifest>, String> {
+    let path = PathBuf::from(&source_path);
+    let managed_dir = get_managed_path(&app, &path)?;
+    if !managed_dir.is_dir() {
+        return Ok(None);
+    }
+    match read_manifest(&managed_dir) {
+        Some(manifest) => Ok(Some(manifest)),
+        None => ensure_managed_dir(&app, &path).map(|dir| read_manifest(&dir)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_transcript_path(app: AppHandle, source_path: String) -> Result<Option<String>, String> {
+    let path = PathBuf::from(&source_path);
+    
+    // First check managed transcript
+    let managed_dir = get_managed_path(&app, &path)?;
+    let managed_transcript = managed_dir.join("transcript.txt");
+    if managed_transcript.exists() {
+        return Ok(Some(managed_transcript.to_string_lossy().to_string()));
+    }
+    
+    // Check sidecar transcript
+    let sidecar_transcript = path.with_extension("txt");
+    if sidecar_transcript.exists() {
+        return Ok(Some(sidecar_transcript.to_string_lossy().to_string()));
+    }
+    
+    Ok(None)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::ops::Deref;
+
+    /// Empty directory in the temp dir for one test, removed on drop so a
+    /// failing test doesn't leave it behind.
+    pub(crate) struct ScratchDir(PathBuf);
+
+    impl Deref for ScratchDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// `name` must be unique among all tests of the crate.
+    pub(crate) fn scratch_dir(name: &str) -> ScratchDir {
+        let dir = std::env::temp_dir().join(format!("open-recorder-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        ScratchDir(dir)
+    }
+
+    #[test]
+    fn audio_source_follows_the_pointer_when_the_source_is_gone() {
+        let base = scratch_dir("storage-pointer");
+        let managed = base.join("managed");
+        fs::create_dir_all(&managed).unwrap();
+        let moved = base.join("moved.mp3");
+        fs::write(&moved, "audio").unwrap();
+        fs::write(managed.join(SOURCE_POINTER_FILE), moved.to_string_lossy().as_bytes()).unwrap();
+
+        assert_eq!(audio_source(&managed, &moved).unwrap(), moved);
+        assert_eq!(audio_source(&managed, &base.join("talk.mp3")).unwrap(), moved);
+        fs::remove_file(&moved).unwrap();
+        assert!(audio_source(&managed, &base.join("talk.mp3")).is_err());
+    }
+}
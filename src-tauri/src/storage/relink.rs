@@ -0,0 +1,199 @@
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use super::{get_storage_root, is_recording_id, recording_id, touch_manifest};
+use crate::audio;
+use crate::library::LibraryIndex;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkedDir {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub recording_id: String,
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkReport {
+    /// Legacy path-named directories renamed to their recording id.
+    pub relinked: Vec<RelinkedDir>,
+    /// Legacy directories whose recording already had a directory. Files the
+    /// target lacked were moved over; anything left stays in `from`.
+    pub merged: Vec<RelinkedDir>,
+    /// Managed directories no indexed library file maps to.
+    pub orphaned: Vec<String>,
+    /// Library files with identical audio, which share one managed directory.
+    pub duplicates: Vec<DuplicateGroup>,
+    /// Directories that couldn't be processed, with the reason.
+    pub errors: Vec<String>,
+}
+
+/// Moves managed directories to their content-hash name and reports
+/// orphans and duplicates against `paths_by_hash` (content hash to library
+/// paths, from the library index).
+pub fn relink(audios_dir: &Path, paths_by_hash: &HashMap<String, Vec<String>>) -> Result<RelinkReport, String> {
+    let mut report = RelinkReport::default();
+    let mut ids = Vec::new();
+
+    let entries = match fs::read_dir(audios_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+        Err(e) => return Err(e.to_string()),
+    };
+    for entry in entries {
+        let dir = entry.map_err(|e| e.to_string())?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if is_recording_id(&name) {
+            ids.push(name);
+            continue;
+        }
+
+        match relink_legacy_dir(audios_dir, &dir, &mut report) {
+            Ok(id) => ids.push(id),
+            Err(e) => {
+                warn!("Failed to relink {:?}: {}", dir, e);
+                report.errors.push(format!("{}: {}", dir.display(), e));
+            }
+        }
+    }
+
+    let known: HashSet<&str> = paths_by_hash.keys().map(|hash| recording_id(hash)).collect();
+    report.orphaned = ids.into_iter().filter(|id| !known.contains(id.as_str())).collect();
+    report.orphaned.sort();
+    report.orphaned.dedup();
+
+    report.duplicates = paths_by_hash
+        .iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(hash, paths)| DuplicateGroup {
+            recording_id: recording_id(hash).to_string(),
+            paths: paths.clone(),
+        })
+        .collect();
+    report.duplicates.sort_by(|a, b| a.paths.cmp(&b.paths));
+
+    Ok(report)
+}
+
+/// Hashes the audio copy in a legacy directory and moves the directory (or
+/// its files) to the recording id. Returns the id.
+fn relink_legacy_dir(audios_dir: &Path, dir: &Path, report: &mut RelinkReport) -> Result<String, String> {
+    let audio_copy = fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .find(|path| path.is_file() && path.file_stem().is_some_and(|stem| stem == "audio"))
+        .ok_or_else(|| "no audio copy to identify the recording".to_string())?;
+
+    let hash = audio::content_hash(&audio_copy)?;
+    let id = recording_id(&hash).to_string();
+    let target = audios_dir.join(&id);
+    let moved = RelinkedDir {
+        from: dir.to_string_lossy().to_string(),
+        to: target.to_string_lossy().to_string(),
+    };
+
+    if !target.exists() {
+        fs::rename(dir, &target).map_err(|e| e.to_string())?;
+        info!("Relinked {:?} to {:?}", dir, target);
+        report.relinked.push(moved);
+        return Ok(id);
+    }
+
+    // Same recording was also stored under its new id; keep the target's
+    // files and take over whatever it is missing (e.g. an older transcript)
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let from = entry.map_err(|e| e.to_string())?.path();
+        let to = target.join(from.file_name().unwrap_or_default());
+        if !to.exists() {
+            fs::rename(&from, &to).map_err(|e| e.to_string())?;
+        } else if from == audio_copy {
+            // Identical audio by definition of the id
+            fs::remove_file(&from).map_err(|e| e.to_string())?;
+        }
+    }
+    // Only succeeds once everything was moved over
+    let _ = fs::remove_dir(dir);
+    touch_manifest(&target);
+    info!("Merged {:?} into {:?}", dir, target);
+    report.merged.push(moved);
+    Ok(id)
+}
+
+/// Reconnects managed directories to library files by content hash: legacy
+/// path-named directories are renamed to their recording id, and orphaned
+/// directories and duplicate library files are reported. Uses the library
+/// index, so scan the libraries first.
+#[tauri::command]
+pub async fn relink_managed_storage(app: AppHandle) -> Result<RelinkReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let audios_dir = get_storage_root(&app)?.join("audios");
+        let paths_by_hash = app.state::<LibraryIndex>().paths_by_hash()?;
+        relink(&audios_dir, &paths_by_hash)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    #[test]
+    fn relinks_legacy_dirs_and_reports_orphans_and_duplicates() {
+        let audios = scratch_dir("relink-audios");
+        let legacy = audios.join("meeting-1a2b3c");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("audio.bin"), b"not really audio").unwrap();
+        fs::write(legacy.join("transcript.txt"), "hello").unwrap();
+        let hash = audio::content_hash(&legacy.join("audio.bin")).unwrap();
+        let id = recording_id(&hash).to_string();
+
+        let orphan = "f".repeat(crate::storage::RECORDING_ID_LEN);
+        fs::create_dir_all(audios.join(&orphan)).unwrap();
+
+        let mut paths_by_hash = HashMap::new();
+        paths_by_hash.insert(hash.clone(), vec!["/rec/meeting.bin".to_string(), "/rec/moved/meeting.bin".to_string()]);
+
+        let report = relink(&audios, &paths_by_hash).unwrap();
+
+        assert_eq!(report.relinked.len(), 1);
+        assert!(audios.join(&id).join("transcript.txt").exists());
+        assert!(!legacy.exists());
+        assert_eq!(report.orphaned, vec![orphan]);
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].recording_id, id);
+    }
+
+    #[test]
+    fn merges_into_existing_recording_dir() {
+        let audios = scratch_dir("relink-merge");
+        let legacy = audios.join("call-abcdef");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("audio.bin"), b"same bytes").unwrap();
+        fs::write(legacy.join("transcript.txt"), "old transcript").unwrap();
+        let id = recording_id(&audio::content_hash(&legacy.join("audio.bin")).unwrap()).to_string();
+        fs::create_dir_all(audios.join(&id)).unwrap();
+        fs::write(audios.join(&id).join("audio.bin"), b"same bytes").unwrap();
+
+        let report = relink(&audios, &HashMap::new()).unwrap();
+
+        assert_eq!(report.merged.len(), 1);
+        assert_eq!(fs::read_to_string(audios.join(&id).join("transcript.txt")).unwrap(), "old transcript");
+        assert!(!legacy.exists());
+    }
+}
@@ -0,0 +1,248 @@
+use log::{info, warn};
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use super::{default_storage_parent, get_storage_root, read_manifest, update_manifest, AudioLink, ROOT_DIR_NAME};
+use crate::insights::EngineCache;
+use crate::jobs::JobQueue;
+use crate::library::{LibraryIndex, LIBRARY_DB_FILE};
+use crate::settings;
+
+/// Moves the storage root to `<parent>/open-recorder`, or back to the default
+/// location when `parent` is `None`. Everything is copied to a staging folder
+/// next to the target and verified file by file before it is renamed into
+/// place and the setting switched over; on any error the old root stays in
+/// use untouched. The old root is deleted afterwards unless `keep_old` is set.
+/// Returns the new root.
+#[tauri::command]
+pub async fn move_storage_root(app: AppHandle, parent: Option<String>, keep_old: bool) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || relocate(&app, parent, keep_old))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn relocate(app: &AppHandle, parent: Option<String>, keep_old: bool) -> Result<String, String> {
+    let parent = parent.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    let current = get_storage_root(app)?;
+    let target_parent = match &parent {
+        Some(p) => PathBuf::from(p),
+        None => default_storage_parent(app)?,
+    };
+    let target = target_parent.join(ROOT_DIR_NAME);
+
+    if target == current {
+        save_storage_root(app, parent)?;
+        return Ok(target.to_string_lossy().to_string());
+    }
+    if target.starts_with(&current) || current.starts_with(&target) {
+        return Err("The new storage location can't be inside the current one or contain it".to_string());
+    }
+    if app.state::<JobQueue>().has_unfinished() {
+        return Err("Wait for background jobs to finish or cancel them before moving storage".to_string());
+    }
+    let target_in_use = fs::read_dir(&target).map(|mut entries| entries.next().is_some()).unwrap_or(false);
+    if target_in_use {
+        return Err(format!("{} already exists and is not empty", target.display()));
+    }
+
+    fs::create_dir_all(&target_parent).map_err(|e| format!("Can't create {}: {}", target_parent.display(), e))?;
+    // Leftover of an interrupted move; never in use
+    let staging = target_parent.join(format!("{}.moving", ROOT_DIR_NAME));
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| e.to_string())?;
+    }
+
+    let index = app.state::<LibraryIndex>();
+    let staged = stage_copy(&current, &staging)
+        .and_then(|_| index.backup_to(&staging.join(LIBRARY_DB_FILE)))
+        .and_then(|_| {
+            if target.exists() {
+                fs::remove_dir(&target).map_err(|e| e.to_string())?;
+            }
+            fs::rename(&staging, &target).map_err(|e| e.to_string())
+        });
+    if let Err(e) = staged {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("Storage was not moved: {}", e));
+    }
+
+    if let Err(e) = save_storage_root(app, parent) {
+        let _ = fs::remove_dir_all(&target);
+        return Err(format!("Storage was not moved: {}", e));
+    }
+    info!("Moved storage root from {:?} to {:?}", current, target);
+
+    // Writes between the backup and here only miss the index, which the
+    // next scan fills in again
+    if let Err(e) = index.reopen(&target.join(LIBRARY_DB_FILE)) {
+        warn!("Failed to reopen library index at {:?}: {}", target, e);
+    }
+    // Engines hold the chunk cache path
+    app.state::<EngineCache>().invalidate();
+
+    if !keep_old && current.exists() {
+        if let Err(e) = fs::remove_dir_all(&current) {
+            warn!("Failed to remove old storage root {:?}: {}", current, e);
+        }
+    }
+
+    Ok(target.to_string_lossy().to_string())
+}
+
+fn save_storage_root(app: &AppHandle, parent: Option<String>) -> Result<(), String> {
+    let mut settings = settings::load_settings(app)?;
+    settings.storage_root = parent;
+    settings::save_settings(app, &settings)
+}
+
+/// Copies `from` to `staging` and checks every copied file against its
+/// original. The library database is skipped; it is copied separately from
+/// the open connection. Hardlinked and reflinked audio is linked again where
+/// the new location allows it; otherwise it is copied and its manifest says so.
+fn stage_copy(from: &Path, staging: &Path) -> Result<usize, String> {
+    fs::create_dir_all(staging).map_err(|e| e.to_string())?;
+    if !from.exists() {
+        return Ok(0);
+    }
+    let mut files = Vec::new();
+    let mut unlinked = Vec::new();
+    copy_dir(from, staging, Path::new(""), &mut files, &mut unlinked)?;
+    for relative in &files {
+        verify_copy(&from.join(relative), &staging.join(relative))?;
+    }
+    // After verifying, the manifests no longer match their originals
+    for relative in &unlinked {
+        update_manifest(&staging.join(relative), |m| m.audio = Some(AudioLink::Copy))?;
+    }
+    Ok(files.len())
+}
+
+/// Links `copy` to the same data as `original` the way `link` did.
+fn link_again(original: &Path, copy: &Path, link: AudioLink) -> bool {
+    match link {
+        AudioLink::Hardlink => fs::hard_link(original, copy).is_ok(),
+        AudioLink::Reflink => reflink_copy::reflink(original, copy).is_ok(),
+        AudioLink::Copy | AudioLink::Reference => false,
+    }
+}
+
+// `unlinked` collects recording directories whose linked audio had to be copied
+fn copy_dir(
+    root: &Path,
+    staging: &Path,
+    relative: &Path,
+    files: &mut Vec<PathBuf>,
+    unlinked: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    let dir = root.join(relative);
+    let link = read_manifest(&dir).and_then(|m| m.audio).filter(|link| link.is_shared());
+    for entry in fs::read_dir(&dir).map_err(|e| format!("Can't read {}: {}", dir.display(), e))? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let name = entry.file_name();
+        let path = relative.join(&name);
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+
+        if file_type.is_dir() {
+            fs::create_dir_all(staging.join(&path)).map_err(|e| e.to_string())?;
+            copy_dir(root, staging, &path, files, unlinked)?;
+        } else if file_type.is_file() {
+            if relative.as_os_str().is_empty() && name.to_string_lossy().starts_with(LIBRARY_DB_FILE) {
+                continue;
+            }
+            let is_audio = Path::new(&name).file_stem().is_some_and(|stem| stem == "audio");
+            if let Some(link) = link.filter(|_| is_audio) {
+                if link_again(&root.join(&path), &staging.join(&path), link) {
+                    files.push(path);
+                    continue;
+                }
+                warn!("Can't {:?} {:?} at the new location, copying it", link, root.join(&path));
+                unlinked.push(relative.to_path_buf());
+            }
+            fs::copy(root.join(&path), staging.join(&path))
+                .map_err(|e| format!("Can't copy {}: {}", root.join(&path).display(), e))?;
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn verify_copy(original: &Path, copy: &Path) -> Result<(), String> {
+    let original_len = fs::metadata(original).map_err(|e| e.to_string())?.len();
+    let copy_len = fs::metadata(copy).map_err(|e| e.to_string())?.len();
+    if original_len != copy_len || file_hash(original)? != file_hash(copy)? {
+        return Err(format!("Copy of {} doesn't match the original", original.display()));
+    }
+    Ok(())
+}
+
+fn file_hash(path: &Path) -> Result<blake3::Hash, String> {
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(BufReader::new(file)).map_err(|e| e.to_string())?;
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    #[test]
+    fn stages_a_verified_copy_without_the_database() {
+        let base = scratch_dir("relocate-stage");
+        let root = base.join("old");
+        fs::create_dir_all(root.join("audios").join("abc")).unwrap();
+        fs::write(root.join("audios").join("abc").join("transcript.txt"), "hello").unwrap();
+        fs::write(root.join("jobs.json"), "[]").unwrap();
+        fs::write(root.join(LIBRARY_DB_FILE), "db").unwrap();
+
+        let staging = base.join("new.moving");
+        let copied = stage_copy(&root, &staging).unwrap();
+
+        assert_eq!(copied, 2);
+        assert_eq!(fs::read_to_string(staging.join("audios").join("abc").join("transcript.txt")).unwrap(), "hello");
+        assert!(!staging.join(LIBRARY_DB_FILE).exists());
+    }
+
+    #[test]
+    fn keeps_hardlinks_and_records_copies() {
+        let base = scratch_dir("relocate-links");
+        let root = base.join("old");
+        let library = base.join("talk.mp3");
+        fs::write(&library, "audio").unwrap();
+        let dir = root.join("audios").join("abc");
+        fs::create_dir_all(&dir).unwrap();
+        fs::hard_link(&library, dir.join("audio.mp3")).unwrap();
+        update_manifest(&dir, |m| m.audio = Some(AudioLink::Hardlink)).unwrap();
+        // A reflink the scratch filesystem may not support again
+        let copied = root.join("audios").join("def");
+        fs::create_dir_all(&copied).unwrap();
+        fs::write(copied.join("audio.mp3"), "other").unwrap();
+        update_manifest(&copied, |m| m.audio = Some(AudioLink::Reflink)).unwrap();
+
+        let staging = base.join("new.moving");
+        stage_copy(&root, &staging).unwrap();
+
+        let staged = staging.join("audios").join("abc");
+        fs::write(&library, "edited").unwrap();
+        assert_eq!(fs::read_to_string(staged.join("audio.mp3")).unwrap(), "edited");
+        assert_eq!(read_manifest(&staged).unwrap().audio, Some(AudioLink::Hardlink));
+        let staged = staging.join("audios").join("def");
+        assert_eq!(fs::read_to_string(staged.join("audio.mp3")).unwrap(), "other");
+        let reflinks = reflink_copy::reflink(copied.join("audio.mp3"), base.join("probe.mp3")).is_ok();
+        let expected = if reflinks { AudioLink::Reflink } else { AudioLink::Copy };
+        assert_eq!(read_manifest(&staged).unwrap().audio, Some(expected));
+    }
+
+    #[test]
+    fn rejects_a_copy_that_differs() {
+        let base = scratch_dir("relocate-verify");
+        fs::write(base.join("a"), "same length").unwrap();
+        fs::write(base.join("b"), "same lengtH").unwrap();
+
+        assert!(verify_copy(&base.join("a"), &base.join("b")).is_err());
+        assert!(verify_copy(&base.join("a"), &base.join("a")).is_ok());
+    }
+}
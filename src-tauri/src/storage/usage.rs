@@ -0,0 +1,574 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use super::manifest::{read_manifest, touch_manifest, MANIFEST_FILE};
+use super::{get_insights_cache_dir, get_storage_root, is_recording_id, recording_id, transcript_hash};
+use crate::library::{LibraryIndex, LIBRARY_DB_FILE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArtifactKind {
+    Audio,
+    Transcript,
+    Insights,
+    Other,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingUsage {
+    pub recording_id: String,
+    /// Library file of the recording: one that still exists if there is
+    /// one, else the last known location.
+    pub source_path: Option<String>,
+    /// No library file for this recording exists anymore. Never set for
+    /// legacy path-named directories; relink them first.
+    pub orphaned: bool,
+    pub audio_bytes: u64,
+    pub transcript_bytes: u64,
+    pub insights_bytes: u64,
+    pub other_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub audio_bytes: u64,
+    pub transcript_bytes: u64,
+    pub insights_bytes: u64,
+    pub other_bytes: u64,
+    /// Insights cache, including map-reduce chunk results.
+    pub cache_bytes: u64,
+    /// Cache entries no recording's insights come from.
+    pub unreferenced_cache_bytes: u64,
+    /// Library index database.
+    pub index_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    pub root: String,
+    pub totals: UsageTotals,
+    /// Largest first.
+    pub recordings: Vec<RecordingUsage>,
+}
+
+/// What `clean_storage` removes.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CleanupOptions {
+    /// Managed directories whose library file is gone.
+    pub orphans: bool,
+    /// With `orphans`, drop only the audio copy and keep transcripts and insights.
+    pub keep_transcripts: bool,
+    /// Audio copies of recordings whose library file still exists; copied
+    /// again when the recording is next transcribed.
+    pub audio_copies: bool,
+    /// Cache entries no recording references, and map-reduce chunk results.
+    pub cache: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CleanupKind {
+    OrphanedRecording,
+    OrphanedAudio,
+    AudioCopy,
+    UnreferencedCache,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupItem {
+    pub kind: CleanupKind,
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    /// False for a dry run: `items` lists what would be removed.
+    pub applied: bool,
+    pub items: Vec<CleanupItem>,
+    pub freed_bytes: u64,
+    pub errors: Vec<String>,
+}
+
+struct ScannedRecording {
+    dir: PathBuf,
+    usage: RecordingUsage,
+    audio_files: Vec<(PathBuf, u64)>,
+    /// Transcript hashes whose cached insights this recording uses: the
+    /// current transcript's and the one its stored insights were made for.
+    transcript_hashes: Vec<String>,
+}
+
+struct ScannedCache {
+    entry: PathBuf,
+    hash: Option<String>,
+    bytes: u64,
+}
+
+/// Disk usage of the storage root, per recording and per artifact type.
+/// Uses the library index to tell which recordings still exist, so scan the
+/// libraries first.
+#[tauri::command]
+pub async fn get_storage_usage(app: AppHandle) -> Result<StorageUsage, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = get_storage_root(&app)?;
+        let sources = library_sources(&app)?;
+        let recordings = scan_recordings(&root.join("audios"), &sources)?;
+        let cache = scan_cache(&get_insights_cache_dir(&app)?)?;
+        let index_bytes = fs::metadata(root.join(LIBRARY_DB_FILE)).map(|m| m.len()).unwrap_or(0);
+        Ok(usage_report(&root, recordings, &cache, index_bytes))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Removes what `options` selects. With `dry_run` nothing is deleted and the
+/// report lists what would be; run it first and show it to the user. To
+/// apply, pass the items they confirmed as `reviewed`: only those that are
+/// still in the plan are removed.
+#[tauri::command]
+pub async fn clean_storage(
+    app: AppHandle,
+    options: CleanupOptions,
+    dry_run: bool,
+    reviewed: Option<Vec<CleanupItem>>,
+) -> Result<CleanupReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = get_storage_root(&app)?;
+        let sources = library_sources(&app)?;
+        let recordings = scan_recordings(&root.join("audios"), &sources)?;
+        let cache = scan_cache(&get_insights_cache_dir(&app)?)?;
+
+        let items = plan_cleanup(&recordings, &cache, options);
+        if dry_run {
+            let freed_bytes = items.iter().map(|item| item.bytes).sum();
+            return Ok(CleanupReport {
+                applied: false,
+                items,
+                freed_bytes,
+                errors: Vec::new(),
+            });
+        }
+        let reviewed = reviewed.ok_or("Run a dry run first and pass the items to remove as `reviewed`")?;
+        let report = apply_cleanup(reviewed_items(items, &reviewed));
+        info!("Storage cleanup freed {} bytes ({} errors)", report.freed_bytes, report.errors.len());
+        Ok(report)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Library files per recording id, from the library index.
+fn library_sources(app: &AppHandle) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut sources: HashMap<String, Vec<String>> = HashMap::new();
+    for (hash, paths) in app.state::<LibraryIndex>().paths_by_hash()? {
+        sources.entry(recording_id(&hash).to_string()).or_default().extend(paths);
+    }
+    Ok(sources)
+}
+
+fn artifact_kind(name: &str) -> ArtifactKind {
+    if Path::new(name).file_stem().is_some_and(|stem| stem == "audio") {
+        return ArtifactKind::Audio;
+    }
+    match name {
+        "transcript.txt" | "transcript.json" => ArtifactKind::Transcript,
+        "insights.json" | "summary.txt" | "actions.json" => ArtifactKind::Insights,
+        _ => ArtifactKind::Other,
+    }
+}
+
+fn scan_recordings(audios_dir: &Path, sources: &HashMap<String, Vec<String>>) -> Result<Vec<ScannedRecording>, String> {
+    let entries = match fs::read_dir(audios_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut recordings = Vec::new();
+    for entry in entries {
+        let dir = entry.map_err(|e| e.to_string())?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let id = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let manifest = read_manifest(&dir);
+        // Linked audio takes no space of its own
+        let shared_audio = manifest.as_ref().and_then(|m| m.audio).is_some_and(|link| link.is_shared());
+
+        // Any library copy keeps the recording alive, the manifest covers
+        // files that were never indexed
+        let known = sources
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .chain(manifest.as_ref().map(|m| m.source_path.clone()).filter(|p| !p.is_empty()));
+        let mut last_known = None;
+        let mut existing = None;
+        for path in known {
+            if Path::new(&path).exists() {
+                existing = Some(path);
+                break;
+            }
+            last_known = Some(path);
+        }
+
+        let mut usage = RecordingUsage {
+            orphaned: existing.is_none() && is_recording_id(&id),
+            recording_id: id,
+            source_path: existing.or(last_known),
+            ..Default::default()
+        };
+        let mut audio_files = Vec::new();
+        for file in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+            let file = file.map_err(|e| e.to_string())?;
+            let Ok(metadata) = file.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let name = file.file_name().to_string_lossy().to_string();
+            let kind = artifact_kind(&name);
+            let bytes = if kind == ArtifactKind::Audio && shared_audio { 0 } else { metadata.len() };
+            match kind {
+                ArtifactKind::Audio => {
+                    usage.audio_bytes += bytes;
+                    audio_files.push((file.path(), bytes));
+                }
+                ArtifactKind::Transcript => usage.transcript_bytes += bytes,
+                ArtifactKind::Insights => usage.insights_bytes += bytes,
+                ArtifactKind::Other => usage.other_bytes += bytes,
+            }
+            usage.total_bytes += bytes;
+        }
+
+        let mut transcript_hashes: Vec<String> = fs::read_to_string(dir.join("transcript.txt"))
+            .ok()
+            .map(|text| transcript_hash(&text))
+            .into_iter()
+            .collect();
+        transcript_hashes.extend(manifest.and_then(|m| m.insights).map(|i| i.transcript_hash));
+
+        recordings.push(ScannedRecording {
+            dir,
+            usage,
+            audio_files,
+            transcript_hashes,
+        });
+    }
+    Ok(recordings)
+}
+
+/// Cache entries: one per insights file, plus each chunk cache directory as a whole.
+fn scan_cache(cache_dir: &Path) -> Result<Vec<ScannedCache>, String> {
+    let entries = match fs::read_dir(cache_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut cache = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            cache.push(ScannedCache {
+                bytes: dir_size(&path),
+                entry: path,
+                hash: None,
+            });
+        } else if let Some(hash) = name.strip_suffix(".txt") {
+            cache.push(ScannedCache {
+                hash: Some(hash.to_string()),
+                bytes: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                entry: path,
+            });
+        }
+    }
+    Ok(cache)
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(m) if m.is_dir() => dir_size(&entry.path()),
+            Ok(m) => m.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn referenced_hashes<'a>(recordings: impl IntoIterator<Item = &'a ScannedRecording>) -> HashSet<&'a str> {
+    recordings
+        .into_iter()
+        .flat_map(|r| r.transcript_hashes.iter().map(String::as_str))
+        .collect()
+}
+
+fn usage_report(root: &Path, recordings: Vec<ScannedRecording>, cache: &[ScannedCache], index_bytes: u64) -> StorageUsage {
+    let referenced = referenced_hashes(&recordings);
+    let mut totals = UsageTotals {
+        index_bytes,
+        ..Default::default()
+    };
+    for entry in cache {
+        totals.cache_bytes += entry.bytes;
+        if !entry.hash.as_deref().is_some_and(|hash| referenced.contains(hash)) {
+            totals.unreferenced_cache_bytes += entry.bytes;
+        }
+    }
+
+    let mut usages: Vec<RecordingUsage> = recordings.into_iter().map(|r| r.usage).collect();
+    for usage in &usages {
+        totals.audio_bytes += usage.audio_bytes;
+        totals.transcript_bytes += usage.transcript_bytes;
+        totals.insights_bytes += usage.insights_bytes;
+        totals.other_bytes += usage.other_bytes;
+    }
+    totals.total_bytes = totals.audio_bytes
+        + totals.transcript_bytes
+        + totals.insights_bytes
+        + totals.other_bytes
+        + totals.cache_bytes
+        + totals.index_bytes;
+    usages.sort_by_key(|usage| std::cmp::Reverse(usage.total_bytes));
+
+    StorageUsage {
+        root: root.to_string_lossy().to_string(),
+        totals,
+        recordings: usages,
+    }
+}
+
+fn plan_cleanup(recordings: &[ScannedRecording], cache: &[ScannedCache], options: CleanupOptions) -> Vec<CleanupItem> {
+    let mut items = Vec::new();
+    let audio_items = |recording: &ScannedRecording, kind: CleanupKind| -> Vec<CleanupItem> {
+        recording
+            .audio_files
+            .iter()
+            .map(|(path, bytes)| CleanupItem {
+                kind,
+                path: path.to_string_lossy().to_string(),
+                bytes: *bytes,
+            })
+            .collect()
+    };
+
+    for recording in recordings {
+        if recording.usage.orphaned && options.orphans {
+            if options.keep_transcripts {
+                items.extend(audio_items(recording, CleanupKind::OrphanedAudio));
+            } else {
+                items.push(CleanupItem {
+                    kind: CleanupKind::OrphanedRecording,
+                    path: recording.dir.to_string_lossy().to_string(),
+                    bytes: recording.usage.total_bytes,
+                });
+            }
+        } else if !recording.usage.orphaned && options.audio_copies {
+            items.extend(audio_items(recording, CleanupKind::AudioCopy));
+        }
+    }
+
+    if options.cache {
+        // Insights of removed recordings no longer count as references
+        let removed: HashSet<&Path> = items
+            .iter()
+            .filter(|item| item.kind == CleanupKind::OrphanedRecording)
+            .map(|item| Path::new(&item.path))
+            .collect();
+        let referenced = referenced_hashes(recordings.iter().filter(|r| !removed.contains(r.dir.as_path())));
+        for entry in cache {
+            if entry.hash.as_deref().is_some_and(|hash| referenced.contains(hash)) {
+                continue;
+            }
+            items.push(CleanupItem {
+                kind: CleanupKind::UnreferencedCache,
+                path: entry.entry.to_string_lossy().to_string(),
+                bytes: entry.bytes,
+            });
+        }
+    }
+    items
+}
+
+/// Items of the fresh `plan` the user confirmed. Reviewed items that are no
+/// longer planned (a recording came back, a cache entry got used) are kept,
+/// and so is anything planned that wasn't shown.
+fn reviewed_items(plan: Vec<CleanupItem>, reviewed: &[CleanupItem]) -> Vec<CleanupItem> {
+    let confirmed: HashSet<(CleanupKind, &str)> = reviewed.iter().map(|item| (item.kind, item.path.as_str())).collect();
+    plan.into_iter()
+        .filter(|item| confirmed.contains(&(item.kind, item.path.as_str())))
+        .collect()
+}
+
+fn apply_cleanup(items: Vec<CleanupItem>) -> CleanupReport {
+    let mut report = CleanupReport {
+        applied: true,
+        ..Default::default()
+    };
+    let mut touched: HashSet<PathBuf> = HashSet::new();
+
+    for item in items {
+        let path = Path::new(&item.path);
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        match removed {
+            Ok(()) => {
+                if matches!(item.kind, CleanupKind::OrphanedAudio | CleanupKind::AudioCopy) {
+                    if let Some(dir) = path.parent() {
+                        touched.insert(dir.to_path_buf());
+                    }
+                }
+                report.freed_bytes += item.bytes;
+                report.items.push(item);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                warn!("Failed to remove {:?}: {}", path, e);
+                report.errors.push(format!("{}: {}", item.path, e));
+            }
+        }
+    }
+
+    for dir in touched.iter().filter(|dir| dir.join(MANIFEST_FILE).exists()) {
+        touch_manifest(dir);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::scratch_dir;
+
+    fn recording(audios: &Path, id: &str) -> PathBuf {
+        let dir = audios.join(id.repeat(crate::storage::RECORDING_ID_LEN));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("audio.mp3"), vec![0u8; 100]).unwrap();
+        fs::write(dir.join("transcript.txt"), "hello").unwrap();
+        dir
+    }
+
+    #[test]
+    fn reports_usage_by_artifact_and_orphans() {
+        let base = scratch_dir("usage-report");
+        let audios = base.join("audios");
+        let source = base.join("live.mp3");
+        fs::write(&source, b"x").unwrap();
+        recording(&audios, "a");
+        recording(&audios, "b");
+        let sources = HashMap::from([
+            ("a".repeat(32), vec![source.to_string_lossy().to_string()]),
+            ("b".repeat(32), vec![base.join("gone.mp3").to_string_lossy().to_string()]),
+        ]);
+
+        let recordings = scan_recordings(&audios, &sources).unwrap();
+        let usage = usage_report(&base, recordings, &[], 0);
+
+        assert_eq!(usage.totals.audio_bytes, 200);
+        assert_eq!(usage.totals.transcript_bytes, 10);
+        let orphaned: Vec<bool> = usage.recordings.iter().map(|r| r.orphaned).collect();
+        assert_eq!(orphaned.iter().filter(|o| **o).count(), 1);
+        let gone = usage.recordings.iter().find(|r| r.orphaned).unwrap();
+        assert_eq!(gone.recording_id, "b".repeat(32));
+    }
+
+    #[test]
+    fn dry_run_plan_then_apply_keeps_transcripts() {
+        let base = scratch_dir("usage-clean");
+        let audios = base.join("audios");
+        let cache_dir = base.join("summaries");
+        let gone = recording(&audios, "c");
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(cache_dir.join("abc.txt"), "{}").unwrap();
+
+        let recordings = scan_recordings(&audios, &HashMap::new()).unwrap();
+        let cache = scan_cache(&cache_dir).unwrap();
+        let options = CleanupOptions {
+            orphans: true,
+            keep_transcripts: true,
+            audio_copies: false,
+            cache: true,
+        };
+        let items = plan_cleanup(&recordings, &cache, options);
+
+        let kinds: Vec<CleanupKind> = items.iter().map(|item| item.kind).collect();
+        assert_eq!(kinds, vec![CleanupKind::OrphanedAudio, CleanupKind::UnreferencedCache]);
+        assert!(gone.join("audio.mp3").exists());
+
+        let report = apply_cleanup(reviewed_items(items.clone(), &items));
+        assert_eq!(report.freed_bytes, 102);
+        assert!(!gone.join("audio.mp3").exists());
+        assert!(gone.join("transcript.txt").exists());
+    }
+
+    #[test]
+    fn cache_of_current_transcripts_is_referenced() {
+        let base = scratch_dir("usage-referenced");
+        let audios = base.join("audios");
+        let cache_dir = base.join("summaries");
+        recording(&audios, "d");
+        fs::create_dir_all(&cache_dir).unwrap();
+        // Insights of the recording's transcript, never copied next to it
+        fs::write(cache_dir.join(format!("{}.txt", transcript_hash("hello"))), "{}").unwrap();
+        fs::write(cache_dir.join("stale.txt"), "{}").unwrap();
+
+        let recordings = scan_recordings(&audios, &HashMap::new()).unwrap();
+        let cache = scan_cache(&cache_dir).unwrap();
+        let options = CleanupOptions {
+            cache: true,
+            ..Default::default()
+        };
+        let items = plan_cleanup(&recordings, &cache, options);
+
+        assert_eq!(items.len(), 1);
+        assert!(items[0].path.ends_with("stale.txt"));
+    }
+
+    #[test]
+    fn applies_only_reviewed_items_still_planned() {
+        let item = |kind, path: &str| CleanupItem {
+            kind,
+            path: path.to_string(),
+            bytes: 1,
+        };
+        let plan = vec![
+            item(CleanupKind::AudioCopy, "/s/a/audio.mp3"),
+            item(CleanupKind::UnreferencedCache, "/s/c/new.txt"),
+            item(CleanupKind::OrphanedRecording, "/s/b"),
+        ];
+        let reviewed = vec![
+            item(CleanupKind::AudioCopy, "/s/a/audio.mp3"),
+            item(CleanupKind::OrphanedAudio, "/s/b/audio.mp3"),
+            item(CleanupKind::UnreferencedCache, "/s/c/gone.txt"),
+        ];
+
+        let applied = reviewed_items(plan, &reviewed);
+
+        assert_eq!(applied, vec![item(CleanupKind::AudioCopy, "/s/a/audio.mp3")]);
+    }
+}
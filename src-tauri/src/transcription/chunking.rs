@@ -0,0 +1,360 @@
+use crate::commands::transcription::{SegmentTimestamp, Transcript, WordTimestamp};
+
+// RMS window used to look for pauses between words
+const SILENCE_WINDOW_SECONDS: f64 = 0.1;
+
+/// A slice of decoded audio sent to the provider as one request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkSpan {
+    /// First sample of the chunk, including the overlap with the previous chunk.
+    pub start: usize,
+    /// One past the last sample of the chunk.
+    pub end: usize,
+    /// First sample this chunk is authoritative for; words before it belong
+    /// to the previous chunk.
+    pub owned_start: usize,
+}
+
+/// Cuts a stream of samples into chunks of at most `max_seconds`, preferring
+/// to cut at the quietest point of the last `search_seconds` of each chunk.
+/// Consecutive chunks overlap by `overlap_seconds` so words on the boundary
+/// are not lost. Holds at most one chunk of samples.
+pub struct Chunker {
+    sample_rate: u32,
+    max_len: usize,
+    overlap: usize,
+    search: usize,
+    // Samples from `start` on, `start` being the first sample of the next chunk
+    buffer: Vec<f32>,
+    start: usize,
+    owned_start: usize,
+}
+
+impl Chunker {
+    pub fn new(sample_rate: u32, max_seconds: f64, overlap_seconds: f64, search_seconds: f64) -> Self {
+        let rate = sample_rate as f64;
+        let max_len = (max_seconds * rate) as usize;
+        Self {
+            sample_rate,
+            max_len,
+            overlap: (overlap_seconds * rate) as usize,
+            search: ((search_seconds * rate) as usize).min(max_len / 2),
+            buffer: Vec::new(),
+            start: 0,
+            owned_start: 0,
+        }
+    }
+
+    /// Appends `samples` and returns the chunks they complete.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<(ChunkSpan, Vec<f32>)> {
+        self.buffer.extend_from_slice(samples);
+        let mut chunks = Vec::new();
+        // A chunk is only cut once more audio follows it; the last one takes the rest
+        while self.buffer.len() > self.max_len {
+            let hard_end = self.max_len;
+            let cut = quietest_point(&self.buffer, hard_end - self.search, hard_end, self.sample_rate);
+            // Never cut inside the overlap, otherwise the next chunk makes no progress
+            let cut = cut.max(self.owned_start - self.start + self.overlap + 1).min(hard_end);
+            let span = ChunkSpan {
+                start: self.start,
+                end: self.start + cut,
+                owned_start: self.owned_start,
+            };
+            chunks.push((span, self.buffer[..cut].to_vec()));
+
+            let next_start = cut.saturating_sub(self.overlap);
+            self.buffer.drain(..next_start);
+            self.start += next_start;
+            self.owned_start = span.end;
+        }
+        chunks
+    }
+
+    /// The last chunk, unless the stream ended on a chunk boundary.
+    pub fn finish(self) -> Option<(ChunkSpan, Vec<f32>)> {
+        let end = self.start + self.buffer.len();
+        if end <= self.owned_start {
+            return None;
+        }
+        let span = ChunkSpan {
+            start: self.start,
+            end,
+            owned_start: self.owned_start,
+        };
+        Some((span, self.buffer))
+    }
+}
+
+fn quietest_point(samples: &[f32], from: usize, to: usize, sample_rate: u32) -> usize {
+    let window = ((SILENCE_WINDOW_SECONDS * sample_rate as f64) as usize).max(1);
+    let mut best = to;
+    let mut best_energy = f32::MAX;
+    let mut pos = from;
+
+    while pos + window <= to {
+        let energy = samples[pos..pos + window].iter().map(|s| s * s).sum::<f32>() / window as f32;
+        if energy < best_energy {
+            best_energy = energy;
+            best = pos + window / 2;
+        }
+        pos += window;
+    }
+
+    best
+}
+
+/// Encodes mono f32 samples as a 16-bit PCM WAV file.
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    out.extend_from_slice(&2u16.to_le_bytes()); // block align
+    out.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    out
+}
+
+/// Merges per-chunk transcripts into one with a continuous timeline.
+///
+/// Word timestamps are shifted by the chunk offset. A word is dropped when it
+/// starts in the overlap owned by the previous chunk or before the last kept
+/// word ended, and the chunk text starts at the first kept word.
+pub fn stitch(chunks: Vec<(ChunkSpan, Transcript)>, sample_rate: u32, total_duration: f32) -> Transcript {
+    let rate = sample_rate as f32;
+    let mut text_parts: Vec<String> = Vec::new();
+    let mut words: Vec<WordTimestamp> = Vec::new();
+    let mut segments: Vec<SegmentTimestamp> = Vec::new();
+    let mut languages: Vec<String> = Vec::new();
+
+    for (span, transcript) in chunks {
+        let offset = span.start as f32 / rate;
+        let boundary = span.owned_start as f32 / rate;
+        let words_from = words.last().map_or(0.0, |w| w.end).max(boundary);
+        let first_kept = transcript
+            .words
+            .iter()
+            .position(|w| w.start + offset >= words_from)
+            .unwrap_or(transcript.words.len());
+        let tokens: Vec<&str> = transcript.text.split_whitespace().collect();
+        let text = tokens[text_start(&tokens, &transcript.words, first_kept)..].join(" ");
+
+        for word in transcript.words.into_iter().skip(first_kept) {
+            words.push(WordTimestamp {
+                word: word.word,
+                start: word.start + offset,
+                end: word.end + offset,
+            });
+        }
+
+        for segment in transcript.segments {
+            let start = segment.start + offset;
+            if start < boundary {
+                continue;
+            }
+            segments.push(SegmentTimestamp {
+                text: segment.text,
+                start,
+                end: segment.end + offset,
+            });
+        }
+
+        if !text.is_empty() {
+            text_parts.push(text);
+        }
+        if !transcript.language.is_empty() {
+            languages.push(transcript.language);
+        }
+    }
+
+    Transcript {
+        text: text_parts.join(" "),
+        words,
+        segments,
+        duration: total_duration,
+        language: most_common(languages).unwrap_or_else(|| "unknown".to_string()),
+    }
+}
+
+/// Index of the text token that `words[first_kept]` was transcribed from.
+/// Tokens are matched to words in order by their letters and digits, so
+/// punctuation in the text doesn't shift the match; when they stop matching,
+/// each word is taken as one token.
+fn text_start(tokens: &[&str], words: &[WordTimestamp], first_kept: usize) -> usize {
+    let normalize = |s: &str| s.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>();
+    let mut matched = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        if matched == first_kept {
+            return index;
+        }
+        let token = normalize(token);
+        if token.is_empty() {
+            continue;
+        }
+        if token != normalize(&words[matched].word) {
+            return first_kept.min(tokens.len());
+        }
+        matched += 1;
+    }
+    tokens.len()
+}
+
+fn most_common(values: Vec<String>) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    // max_by_key keeps the last maximum, so iterate in reverse to favour the first chunk
+    counts.into_iter().rev().max_by_key(|(_, count)| *count).map(|(v, _)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(word: &str, start: f32, end: f32) -> WordTimestamp {
+        WordTimestamp { word: word.to_string(), start, end }
+    }
+
+    #[test]
+    fn chunker_cuts_at_silence_and_overlaps() {
+        let rate = 100;
+        // 30s of noise with a silent gap around 8s
+        let mut samples = vec![0.5_f32; 30 * rate as usize];
+        for s in &mut samples[790..810] {
+            *s = 0.0;
+        }
+
+        let mut chunker = Chunker::new(rate, 10.0, 1.0, 4.0);
+        let mut chunks = Vec::new();
+        for piece in samples.chunks(64) {
+            chunks.extend(chunker.push(piece));
+        }
+        chunks.extend(chunker.finish());
+        let spans: Vec<ChunkSpan> = chunks.iter().map(|(span, _)| *span).collect();
+
+        assert_eq!(spans[0].start, 0);
+        assert!(spans[0].end >= 790 && spans[0].end <= 810);
+        assert_eq!(spans[1].owned_start, spans[0].end);
+        assert_eq!(spans[1].start, spans[0].end - 100);
+        assert_eq!(spans.last().unwrap().end, samples.len());
+        for (span, chunk) in &chunks {
+            assert!(chunk.len() <= 1000);
+            assert_eq!(chunk[..], samples[span.start..span.end]);
+        }
+    }
+
+    #[test]
+    fn chunker_keeps_short_streams_whole() {
+        let mut chunker = Chunker::new(100, 10.0, 1.0, 4.0);
+        assert!(chunker.push(&[0.1; 1000]).is_empty());
+
+        let (span, chunk) = chunker.finish().unwrap();
+
+        assert_eq!(span, ChunkSpan { start: 0, end: 1000, owned_start: 0 });
+        assert_eq!(chunk.len(), 1000);
+        assert!(Chunker::new(100, 10.0, 1.0, 4.0).finish().is_none());
+    }
+
+    #[test]
+    fn stitch_offsets_words_and_drops_overlap() {
+        let first = ChunkSpan { start: 0, end: 1000, owned_start: 0 };
+        let second = ChunkSpan { start: 900, end: 1500, owned_start: 1000 };
+        let chunks = vec![
+            (first, Transcript {
+                text: "hello there".to_string(),
+                words: vec![word("hello", 1.0, 2.0), word("there", 8.0, 9.5)],
+                segments: Vec::new(),
+                duration: 10.0,
+                language: "english".to_string(),
+            }),
+            (second, Transcript {
+                text: "there, general kenobi".to_string(),
+                words: vec![word("there", 0.0, 0.5), word("general", 1.5, 2.0), word("kenobi", 2.5, 3.0)],
+                segments: Vec::new(),
+                duration: 6.0,
+                language: "english".to_string(),
+            }),
+        ];
+
+        let merged = stitch(chunks, 100, 15.0);
+
+        assert_eq!(merged.text, "hello there general kenobi");
+        assert_eq!(merged.words.len(), 4);
+        assert_eq!(merged.words[2].word, "general");
+        assert!((merged.words[2].start - 10.5).abs() < f32::EPSILON);
+        assert_eq!(merged.language, "english");
+        assert_eq!(merged.duration, 15.0);
+    }
+
+    #[test]
+    fn stitch_drops_words_heard_by_both_chunks() {
+        let first = ChunkSpan { start: 0, end: 1000, owned_start: 0 };
+        let second = ChunkSpan { start: 900, end: 1500, owned_start: 1000 };
+        let chunks = vec![
+            (first, Transcript {
+                text: "Well, hello there.".to_string(),
+                words: vec![word("Well", 1.0, 1.5), word("hello", 8.0, 9.0), word("there", 9.2, 10.3)],
+                segments: Vec::new(),
+                duration: 10.0,
+                language: "english".to_string(),
+            }),
+            // "there" starts after the boundary but the first chunk already heard it
+            (second, Transcript {
+                text: "... hello, there. General Kenobi!".to_string(),
+                words: vec![
+                    word("hello", 0.0, 0.4),
+                    word("there", 1.1, 1.3),
+                    word("General", 1.5, 2.0),
+                    word("Kenobi", 2.5, 3.0),
+                ],
+                segments: Vec::new(),
+                duration: 6.0,
+                language: "english".to_string(),
+            }),
+        ];
+
+        let merged = stitch(chunks, 100, 15.0);
+
+        assert_eq!(merged.text, "Well, hello there. General Kenobi!");
+        let words: Vec<&str> = merged.words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(words, vec!["Well", "hello", "there", "General", "Kenobi"]);
+    }
+
+    #[test]
+    fn text_start_falls_back_to_word_count_when_tokens_differ() {
+        let words = vec![word("gonna", 0.0, 0.5), word("go", 0.6, 0.8), word("home", 0.9, 1.2)];
+
+        assert_eq!(text_start(&["going", "to", "go", "home"], &words, 2), 2);
+        assert_eq!(text_start(&["gonna", "go", "home"], &words, 3), 3);
+        assert_eq!(text_start(&["—", "gonna", "go", "home"], &words, 1), 2);
+    }
+
+    #[test]
+    fn encode_wav_writes_header() {
+        let wav = encode_wav(&[0.0, 1.0, -1.0], 16_000);
+
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(i16::from_le_bytes([wav[46], wav[47]]), i16::MAX);
+    }
+}
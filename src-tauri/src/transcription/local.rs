@@ -0,0 +1,128 @@
+use futures::future::BoxFuture;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+use super::{segments_from_words, TranscriptionProvider};
+use crate::audio::{self, WHISPER_SAMPLE_RATE};
+use crate::commands::transcription::{Transcript, TranscriptionError, WordTimestamp};
+
+// Loading a GGML model takes seconds and hundreds of MB, so keep the last one around
+static LOADED_MODEL: OnceLock<Mutex<Option<(PathBuf, Arc<WhisperContext>)>>> = OnceLock::new();
+
+fn load_context(model_path: &Path) -> Result<Arc<WhisperContext>, TranscriptionError> {
+    let cache = LOADED_MODEL.get_or_init(|| Mutex::new(None));
+    let mut guard = cache.lock().map_err(|e| TranscriptionError::TranscriptionFailed(e.to_string()))?;
+
+    if let Some((path, ctx)) = guard.as_ref() {
+        if path == model_path {
+            return Ok(ctx.clone());
+        }
+    }
+
+    let ctx = WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
+        .map_err(|e| TranscriptionError::TranscriptionFailed(format!("Failed to load model {}: {}", model_path.display(), e)))?;
+    let ctx = Arc::new(ctx);
+    *guard = Some((model_path.to_path_buf(), ctx.clone()));
+    Ok(ctx)
+}
+
+/// Runs whisper.cpp in-process on a GGML/GGUF model; audio never leaves the machine.
+pub struct LocalWhisperProvider {
+    model_path: PathBuf,
+    language: Option<String>,
+}
+
+impl LocalWhisperProvider {
+    pub fn new(model_path: PathBuf, language: Option<String>) -> Result<Self, TranscriptionError> {
+        if !model_path.exists() {
+            return Err(TranscriptionError::ModelNotFound(model_path.display().to_string()));
+        }
+        Ok(Self { model_path, language })
+    }
+}
+
+fn transcribe_blocking(model_path: &Path, language: Option<&str>, audio_path: &Path) -> Result<Transcript, TranscriptionError> {
+    let decoded = audio::decode_to_mono(audio_path, WHISPER_SAMPLE_RATE)
+        .map_err(TranscriptionError::DecodeError)?;
+    let ctx = load_context(model_path)?;
+    let mut state = ctx
+        .create_state()
+        .map_err(|e| TranscriptionError::TranscriptionFailed(e.to_string()))?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(language.unwrap_or("auto")));
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    // One segment per word gives us word-level timestamps like verbose_json
+    params.set_token_timestamps(true);
+    params.set_split_on_word(true);
+    params.set_max_len(1);
+
+    state
+        .full(params, &decoded.samples)
+        .map_err(|e| TranscriptionError::TranscriptionFailed(e.to_string()))?;
+
+    let mut words = Vec::new();
+    for segment in state.as_iter() {
+        let text = segment
+            .to_str_lossy()
+            .map_err(|e| TranscriptionError::TranscriptionFailed(e.to_string()))?;
+        let word = text.trim();
+        if word.is_empty() {
+            continue;
+        }
+        // whisper.cpp timestamps are in centiseconds
+        words.push(WordTimestamp {
+            word: word.to_string(),
+            start: segment.start_timestamp() as f32 / 100.0,
+            end: segment.end_timestamp() as f32 / 100.0,
+        });
+    }
+
+    let text = words
+        .iter()
+        .map(|w| w.word.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let language = whisper_rs::get_lang_str(state.full_lang_id_from_state())
+        .unwrap_or("unknown")
+        .to_string();
+
+    Ok(Transcript {
+        text,
+        segments: segments_from_words(&words),
+        words,
+        duration: decoded.duration_seconds() as f32,
+        language,
+    })
+}
+
+impl TranscriptionProvider for LocalWhisperProvider {
+    fn name(&self) -> &'static str {
+        "whisper.cpp"
+    }
+
+    fn model(&self) -> &str {
+        self.model_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    fn transcribe<'a>(&'a self, audio_path: &'a Path) -> BoxFuture<'a, Result<Transcript, TranscriptionError>> {
+        let model_path = self.model_path.clone();
+        let language = self.language.clone();
+        let audio_path = audio_path.to_path_buf();
+
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                transcribe_blocking(&model_path, language.as_deref(), &audio_path)
+            })
+            .await
+            .map_err(|e| TranscriptionError::TranscriptionFailed(e.to_string()))?
+        })
+    }
+}
@@ -0,0 +1,118 @@
+use futures::future::BoxFuture;
+use std::path::Path;
+
+use crate::commands::transcription::{SegmentTimestamp, Transcript, TranscriptionError, WordTimestamp};
+use crate::settings::{AppSettings, TranscriptionProviderKind};
+
+mod chunking;
+#[cfg(feature = "local-whisper")]
+mod local;
+mod openai;
+
+#[cfg(feature = "local-whisper")]
+pub use local::LocalWhisperProvider;
+pub use openai::OpenAiProvider;
+
+/// A speech-to-text backend that turns an audio file into a `Transcript`.
+///
+/// Implementations receive the managed copy of the audio and must not write
+/// any artifacts themselves; persisting the result is the caller's job.
+pub trait TranscriptionProvider: Send + Sync {
+    /// Short identifier used in logs, e.g. "openai".
+    fn name(&self) -> &'static str;
+
+    /// Model the backend runs, recorded in the recording manifest.
+    fn model(&self) -> &str;
+
+    fn transcribe<'a>(&'a self, audio_path: &'a Path) -> BoxFuture<'a, Result<Transcript, TranscriptionError>>;
+}
+
+// Pause that starts a new segment even without sentence punctuation
+#[cfg(any(feature = "local-whisper", test))]
+const SEGMENT_PAUSE_SECONDS: f32 = 1.5;
+
+/// Groups words into sentence-like segments for backends that only report
+/// word timings. A segment ends at `.`, `?` or `!`, or at a long pause.
+#[cfg(any(feature = "local-whisper", test))]
+pub fn segments_from_words(words: &[WordTimestamp]) -> Vec<SegmentTimestamp> {
+    let mut segments = Vec::new();
+    let mut current: Vec<&WordTimestamp> = Vec::new();
+
+    for (i, word) in words.iter().enumerate() {
+        current.push(word);
+        let sentence_end = word.word.ends_with(['.', '?', '!']);
+        let pause = words
+            .get(i + 1)
+            .map(|next| next.start - word.end >= SEGMENT_PAUSE_SECONDS)
+            .unwrap_or(true);
+
+        if sentence_end || pause {
+            segments.push(SegmentTimestamp {
+                text: current.iter().map(|w| w.word.as_str()).collect::<Vec<_>>().join(" "),
+                start: current[0].start,
+                end: word.end,
+            });
+            current.clear();
+        }
+    }
+
+    segments
+}
+
+pub fn provider_from_settings(settings: &AppSettings) -> Result<Box<dyn TranscriptionProvider>, TranscriptionError> {
+    match settings.transcription_provider {
+        TranscriptionProviderKind::OpenAi => Ok(Box::new(OpenAiProvider::from_settings(settings)?)),
+        TranscriptionProviderKind::Local => local_provider(settings),
+    }
+}
+
+#[cfg(feature = "local-whisper")]
+fn local_provider(settings: &AppSettings) -> Result<Box<dyn TranscriptionProvider>, TranscriptionError> {
+    let model_path = settings
+        .whisper_model_path
+        .as_ref()
+        .ok_or_else(|| TranscriptionError::SettingsError("No Whisper model path configured".to_string()))?;
+    let provider = LocalWhisperProvider::new(model_path.into(), settings.whisper_language.clone())?;
+    Ok(Box::new(provider))
+}
+
+#[cfg(not(feature = "local-whisper"))]
+fn local_provider(_settings: &AppSettings) -> Result<Box<dyn TranscriptionProvider>, TranscriptionError> {
+    Err(TranscriptionError::SettingsError(
+        "Local transcription is not available: app was built without the `local-whisper` feature".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(word: &str, start: f32, end: f32) -> WordTimestamp {
+        WordTimestamp {
+            word: word.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn splits_segments_at_sentence_ends_and_pauses() {
+        let words = vec![
+            word("Hello", 0.0, 0.4),
+            word("there.", 0.5, 0.9),
+            word("How", 1.0, 1.2),
+            word("are", 1.3, 1.5),
+            word("you", 3.5, 3.8),
+            word("doing?", 3.9, 4.3),
+            word("Fine", 4.4, 4.8),
+        ];
+
+        let segments = segments_from_words(&words);
+
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["Hello there.", "How are", "you doing?", "Fine"]);
+        assert_eq!((segments[1].start, segments[1].end), (1.0, 1.5));
+        assert_eq!((segments[3].start, segments[3].end), (4.4, 4.8));
+        assert!(segments_from_words(&[]).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use async_openai::{
+    config::OpenAIConfig,
+    error::OpenAIError,
+    types::audio::{
+        AudioInput,
+        AudioResponseFormat,
+        CreateTranscriptionRequestArgs,
+        TimestampGranularity,
+    },
+    Client,
+};
+use futures::future::BoxFuture;
+use log::info;
+use std::path::Path;
+use tokio::sync::mpsc;
+
+use super::chunking::{self, ChunkSpan, Chunker};
+use super::TranscriptionProvider;
+use crate::audio::{self, formats, WHISPER_SAMPLE_RATE};
+use crate::commands::transcription::{SegmentTimestamp, Transcript, TranscriptionError, WordTimestamp};
+use crate::retry::{self, RetryPolicy};
+use crate::settings::AppSettings;
+
+// Upload limit of the OpenAI audio endpoints
+const MAX_UPLOAD_BYTES: u64 = 25 * 1024 * 1024;
+// 10 minutes of 16 kHz 16-bit mono WAV is ~19 MB, safely under the limit
+const CHUNK_SECONDS: f64 = 600.0;
+const CHUNK_OVERLAP_SECONDS: f64 = 2.0;
+const CHUNK_SILENCE_SEARCH_SECONDS: f64 = 30.0;
+// whisper-1 only looks at the last 224 tokens of the prompt
+const PROMPT_TAIL_CHARS: usize = 500;
+
+pub struct OpenAiProvider {
+    client: Client<OpenAIConfig>,
+    model: String,
+    retry: RetryPolicy,
+}
+
+impl OpenAiProvider {
+    pub fn from_settings(settings: &AppSettings) -> Result<Self, TranscriptionError> {
+        let config = settings.openai_config().ok_or(TranscriptionError::MissingApiKey)?;
+
+        Ok(Self {
+            client: retry::openai_client(config),
+            model: settings.transcription_model.clone(),
+            retry: RetryPolicy::from_settings(settings),
+        })
+    }
+
+    async fn transcribe_file(&self, audio_path: &Path) -> Result<Transcript, TranscriptionError> {
+        let metadata = std::fs::metadata(audio_path)?;
+        if !formats::lookup(audio_path).is_some_and(|format| format.uploadable) {
+            info!("{:?} is not a format the API accepts, uploading it as WAV", audio_path);
+            return self.transcribe_chunked(audio_path).await;
+        }
+        if metadata.len() <= MAX_UPLOAD_BYTES {
+            return self.transcribe_input(audio_path.into(), None).await;
+        }
+
+        info!("{:?} exceeds the 25 MB upload limit, transcribing in chunks", audio_path);
+        self.transcribe_chunked(audio_path).await
+    }
+
+    async fn transcribe_chunked(&self, audio_path: &Path) -> Result<Transcript, TranscriptionError> {
+        // Decoding runs at most one chunk ahead of the uploads, so memory use
+        // depends on the chunk size rather than the length of the recording
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let path = audio_path.to_path_buf();
+        let decoder = tokio::task::spawn_blocking(move || decode_chunks(&path, sender));
+
+        let mut results: Vec<(ChunkSpan, Transcript)> = Vec::new();
+        while let Some((span, wav)) = receiver.recv().await {
+            let index = results.len();
+            info!("Transcribing chunk {}", index + 1);
+            let input = AudioInput::from_vec_u8(format!("chunk-{:03}.wav", index), wav);
+
+            // Feed the end of the previous chunk as context so wording stays consistent
+            let prompt = results.last().map(|(_, prev)| {
+                let chars: Vec<char> = prev.text.chars().collect();
+                chars[chars.len().saturating_sub(PROMPT_TAIL_CHARS)..].iter().collect::<String>()
+            });
+
+            let transcript = self.transcribe_input(input, prompt).await?;
+            results.push((span, transcript));
+        }
+
+        let samples = decoder
+            .await
+            .map_err(|e| TranscriptionError::TranscriptionFailed(e.to_string()))?
+            .map_err(TranscriptionError::DecodeError)?;
+        let duration = samples as f64 / WHISPER_SAMPLE_RATE as f64;
+        Ok(chunking::stitch(results, WHISPER_SAMPLE_RATE, duration as f32))
+    }
+
+    async fn transcribe_input(&self, input: AudioInput, prompt: Option<String>) -> Result<Transcript, TranscriptionError> {
+        // Word timestamps need verbose_json, which OpenAI only serves for whisper-1;
+        // self-hosted servers (faster-whisper, LocalAI) accept it for any model
+        let mut args = CreateTranscriptionRequestArgs::default();
+        args.file(input)
+            .model(&self.model)
+            .response_format(AudioResponseFormat::VerboseJson)
+            .timestamp_granularities(&[TimestampGranularity::Word, TimestampGranularity::Segment]);
+        if let Some(prompt) = prompt {
+            args.prompt(prompt);
+        }
+        let request = args
+            .build()
+            .map_err(|e: OpenAIError| TranscriptionError::RequestError(e.to_string()))?;
+
+        let client = &self.client;
+        let response = retry::with_retry(&self.retry, "Transcription request", || {
+            let request = request.clone();
+            async move { client.audio().transcription().create_verbose_json(request).await }
+        })
+        .await?;
+
+        // Extract words from response (words field is available in verbose_json)
+        let words = response
+            .words
+            .unwrap_or_default()
+            .into_iter()
+            .map(|w| WordTimestamp {
+                word: w.word,
+                start: w.start,
+                end: w.end,
+            })
+            .collect();
+        let segments = response
+            .segments
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| SegmentTimestamp {
+                text: s.text.trim().to_string(),
+                start: s.start,
+                end: s.end,
+            })
+            .collect();
+
+        Ok(Transcript {
+            text: response.text,
+            words,
+            segments,
+            duration: response.duration,
+            language: response.language,
+        })
+    }
+}
+
+/// Decodes `path` to WHISPER_SAMPLE_RATE mono and sends it chunk by chunk
+/// as WAV files. Returns the number of samples decoded.
+fn decode_chunks(path: &Path, sender: mpsc::Sender<(ChunkSpan, Vec<u8>)>) -> Result<usize, String> {
+    let send = |(span, samples): (ChunkSpan, Vec<f32>)| {
+        let wav = chunking::encode_wav(&samples, WHISPER_SAMPLE_RATE);
+        // Only fails when the upload side stopped early
+        sender.blocking_send((span, wav)).map_err(|_| "Transcription was stopped".to_string())
+    };
+
+    let mut chunker = Chunker::new(
+        WHISPER_SAMPLE_RATE,
+        CHUNK_SECONDS,
+        CHUNK_OVERLAP_SECONDS,
+        CHUNK_SILENCE_SEARCH_SECONDS,
+    );
+    let samples = audio::decode_mono_stream(path, WHISPER_SAMPLE_RATE, |decoded| {
+        chunker.push(decoded).into_iter().try_for_each(send)
+    })?;
+    chunker.finish().map_or(Ok(()), send)?;
+    Ok(samples)
+}
+
+impl TranscriptionProvider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn transcribe<'a>(&'a self, audio_path: &'a Path) -> BoxFuture<'a, Result<Transcript, TranscriptionError>> {
+        Box::pin(self.transcribe_file(audio_path))
+    }
+}
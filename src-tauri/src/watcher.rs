@@ -0,0 +1,212 @@
+use log::{info, warn};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::audio::formats;
+use crate::jobs::{JobKind, JobQueue};
+use crate::library::{self, LibraryIndex};
+use crate::settings::{self, AppSettings};
+use crate::storage;
+use crate::AudioItem;
+
+/// Event emitted when a watched folder changes, payload is a `LibraryEvent`.
+pub const LIBRARY_EVENT: &str = "library-changed";
+
+// A file is processed once it has seen no events for this long, so
+// recordings that are still being written aren't probed half-finished
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LibraryChange {
+    Added,
+    Changed,
+    Removed,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryEvent {
+    pub change: LibraryChange,
+    pub path: String,
+    /// Present for added and changed recordings.
+    pub item: Option<AudioItem>,
+    /// Id of the transcription job queued for a new recording, if any.
+    pub job_id: Option<String>,
+}
+
+/// Watches the library folders from settings and keeps the library index in
+/// sync with them. Registered as Tauri state in `run()`.
+#[derive(Default)]
+pub struct LibraryWatcher {
+    // Dropping the watcher stops it and ends its event task
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl LibraryWatcher {
+    /// (Re)starts watching `settings.watched_folders`; called on startup and
+    /// whenever settings change.
+    pub fn watch(&self, app: &AppHandle, settings: &AppSettings) {
+        let Ok(mut current) = self.watcher.lock() else {
+            return;
+        };
+        *current = None;
+
+        let folders: Vec<PathBuf> = settings
+            .watched_folders
+            .iter()
+            .map(PathBuf::from)
+            .filter(|folder| folder.is_dir())
+            .collect();
+        if folders.is_empty() {
+            return;
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Library watcher error: {}", e),
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("Failed to start library watcher: {}", e);
+                return;
+            }
+        };
+
+        for folder in &folders {
+            match watcher.watch(folder, RecursiveMode::Recursive) {
+                Ok(()) => info!("Watching {:?}", folder),
+                Err(e) => warn!("Failed to watch {:?}: {}", folder, e),
+            }
+        }
+
+        tauri::async_runtime::spawn(process_events(app.clone(), rx));
+        *current = Some(watcher);
+    }
+}
+
+/// Collects paths from the watcher and handles each one after it settles.
+/// Ends when the watcher is dropped.
+async fn process_events(app: AppHandle, mut rx: UnboundedReceiver<PathBuf>) {
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+
+    loop {
+        match tokio::time::timeout(POLL_INTERVAL, rx.recv()).await {
+            Ok(Some(path)) => {
+                pending.insert(path, Instant::now());
+            }
+            Ok(None) => break,
+            Err(_) => {}
+        }
+
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, seen)| seen.elapsed() >= SETTLE_TIME)
+            .map(|(path, _)| path.clone())
+            .collect();
+        if settled.is_empty() {
+            continue;
+        }
+        for path in &settled {
+            pending.remove(path);
+        }
+
+        let task_app = app.clone();
+        let handled = tauri::async_runtime::spawn_blocking(move || {
+            for path in settled {
+                handle_path(&task_app, &path);
+            }
+        })
+        .await;
+        if let Err(e) = handled {
+            warn!("Library watcher task failed: {}", e);
+        }
+    }
+}
+
+fn handle_path(app: &AppHandle, path: &Path) {
+    // Ignore our own managed copies if the storage root is inside a library
+    if let Ok(root) = storage::get_storage_root(app) {
+        if path.starts_with(&root) {
+            return;
+        }
+    }
+
+    let index = app.state::<LibraryIndex>();
+    if !path.exists() {
+        // Covers deleted files, the old name of renamed ones and deleted folders
+        match index.remove_under(path) {
+            Ok(removed) => {
+                for removed_path in removed {
+                    emit(app, LibraryChange::Removed, removed_path, None, None);
+                }
+            }
+            Err(e) => warn!("Failed to remove {:?} from library index: {}", path, e),
+        }
+        return;
+    }
+    if path.is_dir() {
+        // A folder moved in; index everything below it
+        let mut files = Vec::new();
+        if let Err(e) = library::walk_audio_files(path, &mut files) {
+            warn!("Failed to index {:?}: {}", path, e);
+        }
+        for (file, _, _) in files {
+            handle_file(app, &index, &file);
+        }
+        return;
+    }
+    if formats::is_supported(path) {
+        handle_file(app, &index, path);
+    }
+}
+
+fn handle_file(app: &AppHandle, index: &LibraryIndex, path: &Path) {
+    let is_new = !index.contains(path).unwrap_or(false);
+    let recording = match index.refresh_file(app, path) {
+        Ok(recording) => recording,
+        Err(e) => {
+            warn!("Failed to index {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    let mut job_id = None;
+    if is_new && !recording.has_transcript {
+        let auto_transcribe = settings::load_settings(app).map(|s| s.auto_transcribe).unwrap_or(false);
+        if auto_transcribe {
+            let queue = app.state::<JobQueue>();
+            job_id = Some(queue.enqueue(app, JobKind::Transcription, path.to_path_buf()));
+            info!("Queued transcription for new recording {:?}", path);
+        }
+    }
+
+    let change = if is_new { LibraryChange::Added } else { LibraryChange::Changed };
+    emit(app, change, recording.path.clone(), Some(recording.to_audio_item()), job_id);
+}
+
+fn emit(app: &AppHandle, change: LibraryChange, path: String, item: Option<AudioItem>, job_id: Option<String>) {
+    let event = LibraryEvent {
+        change,
+        path,
+        item,
+        job_id,
+    };
+    if let Err(e) = app.emit(LIBRARY_EVENT, &event) {
+        warn!("Failed to emit library event: {}", e);
+    }
+}
@@ -9,11 +9,17 @@ const Player: any = () => null;
 const Transcription: any = () => null;
 import { Settings, RefreshCw, Search, Loader } from 'lucide-react'
 import { Toaster } from 'react-hot-toast'
-import { pickFolder, scanFolderForAudio } from '@/lib/fs/commands'
+import { onLibraryChanged, pickFolder, scanFolderForAudio } from '@/lib/fs/commands'
 import { getLastFolder, setLastFolder } from '@/lib/fs/config'
 import { readTranscript } from '@/lib/transcription/commands'
 import { AudioItem } from '@/lib/types'
 
+// True if `path` is `folder` or inside it, for either path separator
+function isInFolder(path: string, folder: string) {
+  const base = folder.replace(/[\\/]+$/, '')
+  return path === base || path.startsWith(base + '/') || path.startsWith(base + '\\')
+}
+
 export function Dashboard() {
   const [recordings, setRecordings] = useState<AudioItem[]>([])
   const [selectedId, setSelectedId] = useState<string | null>(null)
@@ -22,6 +28,8 @@ export function Dashboard() {
   const [searchResults, setSearchResults] = useState<string[]>([])
   const [searchLoading, setSearchLoading] = useState(false)
   const transcriptCache = useRef<Map<string, string>>(new Map())
+  // Folder the list shows; read by the library listener, which is set up once
+  const currentFolder = useRef<string | null>(null)
 
   // Debounced transcript search
   useEffect(() => {
@@ -77,6 +85,22 @@ export function Dashboard() {
     loadLastFolder()
   }, [])
 
+  // Keep the list in sync with the watched folders
+  useEffect(() => {
+    const unlisten = onLibraryChanged(({ change, path, item }) => {
+      // Other watched folders aren't shown
+      if (!currentFolder.current || !isInFolder(path, currentFolder.current)) return
+      setRecordings(prev => {
+        const rest = prev.filter(r => r.path !== path)
+        if (change === 'removed' || !item) return rest
+        return change === 'added' ? [...rest, item] : prev.map(r => (r.path === path ? item : r))
+      })
+    })
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [])
+
   // Mock recording for development testing
   useEffect(() => {
     if (typeof window !== 'undefined' && process.env.NODE_ENV === 'development' && recordings.length === 0) {
@@ -122,6 +146,7 @@ export function Dashboard() {
     setLoading(true)
     try {
       const items = await scanFolderForAudio(folderPath)
+      currentFolder.current = folderPath
       setRecordings(items)
       if (items.length > 0 && !selectedId) {
         setSelectedId(items[0].id)
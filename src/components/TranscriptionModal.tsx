@@ -116,7 +116,7 @@ export function TranscriptionModal({ transcript, audioPath, onSaveTranscript, on
   const handleSummarize = async () => {
     setLoadingSummary(true)
     try {
-      const result = await summarizeTranscript(transcript.text)
+      const result = await summarizeTranscript(transcript.text, audioPath)
       setSummary(result)
       toast.success('Summary generated')
     } catch (error) {
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { AudioItem } from '../types';
 
 export async function pickFolder(): Promise<string> {
@@ -12,3 +13,21 @@ export async function scanFolderForAudio(folderPath: string): Promise<AudioItem[
 export async function readFileMeta(filePath: string): Promise<AudioItem> {
   return invoke<AudioItem>('read_file_meta', { filePath });
 }
+
+export type LibraryChange = 'added' | 'changed' | 'removed';
+
+export interface LibraryEvent {
+  change: LibraryChange;
+  path: string;
+  /** Present for added and changed recordings */
+  item?: AudioItem | null;
+  /** Transcription job queued for a new recording when auto-transcribe is on */
+  jobId?: string | null;
+}
+
+/**
+ * Subscribes to recordings appearing, changing or disappearing in the watched folders.
+ */
+export async function onLibraryChanged(handler: (event: LibraryEvent) => void): Promise<UnlistenFn> {
+  return listen<LibraryEvent>('library-changed', (event) => handler(event.payload));
+}
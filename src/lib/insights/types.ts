@@ -1,3 +1,5 @@
+import type { RecordedAtSource } from '../types';
+
 export type InsightsRangePreset = '7d' | '30d' | '90d' | 'all';
 
 export interface InsightsSeriesPoint {
@@ -27,7 +29,11 @@ export interface InsightsRecordingRow {
   name: string;
   path: string;
   mtimeUnix: number;
+  /** Used for the timeline and the preset filter */
+  recordedAtUnix: number;
+  recordedAtSource: RecordedAtSource;
   durationSeconds: number | null;
+  durationEstimated: boolean;
   hasTranscript: boolean;
   language: string;
 }
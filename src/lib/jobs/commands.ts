@@ -0,0 +1,57 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
+
+export type JobKind = 'transcription' | 'insights'
+
+export type JobStatus = 'queued' | 'running' | 'done' | 'failed' | 'cancelled'
+
+export interface JobInfo {
+  id: string
+  kind: JobKind
+  path: string
+  status: JobStatus
+  error?: string | null
+  createdAt: number
+  finishedAt?: number | null
+  /** Times the job was started, across app restarts */
+  attempts: number
+}
+
+/**
+ * Queues background transcription of the given files.
+ * @returns Job IDs in the same order as `filePaths`
+ */
+export async function enqueueTranscriptions(filePaths: string[]): Promise<string[]> {
+  return invoke<string[]>('enqueue_transcriptions', { paths: filePaths })
+}
+
+/**
+ * Queues background insight generation (summary, actions, topics) for transcribed files.
+ * @returns Job IDs in the same order as `filePaths`
+ */
+export async function enqueueInsights(filePaths: string[]): Promise<string[]> {
+  return invoke<string[]>('enqueue_insights', { paths: filePaths })
+}
+
+/**
+ * Cancels a queued or running job.
+ * @returns false if the job is unknown or already finished
+ */
+export async function cancelJob(jobId: string): Promise<boolean> {
+  return invoke<boolean>('cancel_job', { jobId })
+}
+
+export async function listJobs(): Promise<JobInfo[]> {
+  return invoke<JobInfo[]>('list_jobs')
+}
+
+export async function clearFinishedJobs(): Promise<void> {
+  return invoke('clear_finished_jobs')
+}
+
+/**
+ * Subscribes to job state changes (queued/running/done/failed/cancelled).
+ */
+export async function onJobUpdated(handler: (job: JobInfo) => void): Promise<UnlistenFn> {
+  return listen<JobInfo>('job-updated', (event) => handler(event.payload))
+}
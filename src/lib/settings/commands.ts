@@ -0,0 +1,54 @@
+import { invoke } from '@tauri-apps/api/core'
+
+export type TranscriptionProviderKind = 'openai' | 'local'
+
+export type ChatProviderKind = 'openai' | 'ollama'
+
+/** copy: audio is copied into managed storage; reference: only linked or pointed to */
+export type AudioStorageMode = 'copy' | 'reference'
+
+export interface AppSettings {
+  transcriptionProvider: TranscriptionProviderKind
+  whisperModelPath?: string | null
+  whisperLanguage?: string | null
+  /** OpenAI-compatible server (vLLM, LocalAI, Ollama); null uses api.openai.com */
+  openaiBaseUrl?: string | null
+  /** Falls back to the OPENAI_API_KEY environment variable when empty */
+  openaiApiKey?: string | null
+  transcriptionModel: string
+  chatModel: string
+  /** Context window of chatModel in tokens; longer transcripts are summarized in chunks */
+  chatContextTokens: number
+  chatProvider: ChatProviderKind
+  /** Per-library override of chatProvider, keyed by library folder */
+  libraryChatProviders: Record<string, ChatProviderKind>
+  ollamaBaseUrl: string
+  ollamaModel: string
+  /** Context window requested from Ollama; also sets the chunk size for long transcripts */
+  ollamaContextTokens: number
+  /** Library folders watched for new, renamed and deleted recordings */
+  watchedFolders: string[]
+  /** Queue transcription for recordings that appear in a watched folder */
+  autoTranscribe: boolean
+  /** Number of background jobs that may run at the same time */
+  jobConcurrency: number
+  /** Folder holding the open-recorder storage directory; null uses the app data dir. Change it with moveStorageRoot */
+  storageRoot?: string | null
+  audioStorage: AudioStorageMode
+  /** Retries after the first attempt for rate-limited or transient API errors */
+  maxRetries: number
+  /** Total seconds one API call may spend retrying */
+  retryBudgetSeconds: number
+}
+
+export async function getSettings(): Promise<AppSettings> {
+  return invoke<AppSettings>('get_settings')
+}
+
+export async function updateSettings(settings: AppSettings): Promise<AppSettings> {
+  return invoke<AppSettings>('update_settings', { settings })
+}
+
+export async function resetSettings(): Promise<AppSettings> {
+  return invoke<AppSettings>('reset_settings')
+}
@@ -0,0 +1,200 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { RecordedAtSource } from '../types'
+
+export interface RelinkedDir {
+  from: string
+  to: string
+}
+
+export interface DuplicateGroup {
+  recordingId: string
+  paths: string[]
+}
+
+export interface RelinkReport {
+  /** Legacy path-named directories renamed to their recording id */
+  relinked: RelinkedDir[]
+  /** Legacy directories merged into an existing directory for the same recording */
+  merged: RelinkedDir[]
+  /** Managed directories no library file maps to */
+  orphaned: string[]
+  /** Library files with identical audio, sharing one managed directory */
+  duplicates: DuplicateGroup[]
+  errors: string[]
+}
+
+/**
+ * Reconnects moved or renamed recordings to their managed directories by
+ * content hash, and reports orphaned directories and duplicate files.
+ * Scan the library folders first; the report is based on the library index.
+ */
+export async function relinkManagedStorage(): Promise<RelinkReport> {
+  return invoke<RelinkReport>('relink_managed_storage')
+}
+
+export interface ManifestArtifact {
+  name: string
+  size: number
+  modifiedAt: number
+}
+
+export interface RecordingManifest {
+  version: number
+  recordingId: string
+  contentHash: string
+  /** Library file the recording was last seen at */
+  sourcePath: string
+  createdAt: number
+  updatedAt: number
+  probe: {
+    probeVersion: number
+    ext: string
+    size: number
+    duration: number | null
+    durationEstimated: boolean
+    format: { codec: string | null; sampleRate: number | null; channels: number | null; bitrate: number | null }
+    tags: { title: string | null; artist: string | null; date: string | null; comment: string | null; hasCoverArt: boolean }
+    recordedAt: { unix: number; source: RecordedAtSource } | null
+  } | null
+  /** How the audio is kept; reference means only source.path points at the library file */
+  audio: 'copy' | 'reflink' | 'hardlink' | 'reference' | null
+  transcription: {
+    provider: string
+    model: string
+    language: string
+    transcribedAt: number
+    editedAt: number | null
+  } | null
+  /** Insights stored with the recording and the transcript version they belong to */
+  insights: {
+    transcriptHash: string
+    generatedAt: number
+    model: string | null
+  } | null
+  artifacts: ManifestArtifact[]
+}
+
+/**
+ * Returns the manifest of a recording's managed directory: where the audio came
+ * from, how it was transcribed and which artifacts exist. Null if the recording
+ * was never imported.
+ */
+export async function getRecordingManifest(sourcePath: string): Promise<RecordingManifest | null> {
+  return invoke<RecordingManifest | null>('get_recording_manifest', { sourcePath })
+}
+
+/**
+ * Moves all managed recordings, insights and caches to `<parent>/open-recorder`,
+ * or back to the default location when `parent` is null. Data is copied and
+ * verified before the app switches over; the old folder is removed unless
+ * `keepOld` is set. Resolves to the new storage root.
+ */
+export async function moveStorageRoot(parent: string | null, keepOld = false): Promise<string> {
+  return invoke<string>('move_storage_root', { parent, keepOld })
+}
+
+export interface RecordingUsage {
+  recordingId: string
+  /** Existing library file of the recording, else its last known location */
+  sourcePath: string | null
+  /** No library file for this recording exists anymore */
+  orphaned: boolean
+  audioBytes: number
+  transcriptBytes: number
+  insightsBytes: number
+  otherBytes: number
+  totalBytes: number
+}
+
+export interface StorageUsage {
+  root: string
+  totals: {
+    audioBytes: number
+    transcriptBytes: number
+    insightsBytes: number
+    otherBytes: number
+    cacheBytes: number
+    unreferencedCacheBytes: number
+    indexBytes: number
+    totalBytes: number
+  }
+  /** Largest first */
+  recordings: RecordingUsage[]
+}
+
+export interface CleanupOptions {
+  /** Managed folders whose library file is gone */
+  orphans?: boolean
+  /** With orphans, drop only the audio copy and keep transcripts and insights */
+  keepTranscripts?: boolean
+  /** Audio copies of recordings that still exist in the library */
+  audioCopies?: boolean
+  /** Cached insights no recording uses, and map-reduce chunk results */
+  cache?: boolean
+}
+
+export type CleanupKind = 'orphanedRecording' | 'orphanedAudio' | 'audioCopy' | 'unreferencedCache'
+
+export interface CleanupItem {
+  kind: CleanupKind
+  path: string
+  bytes: number
+}
+
+export interface CleanupReport {
+  /** False for a dry run, items then lists what would be removed */
+  applied: boolean
+  items: CleanupItem[]
+  freedBytes: number
+  errors: string[]
+}
+
+/**
+ * Disk usage of managed storage per recording and per artifact type.
+ */
+export async function getStorageUsage(): Promise<StorageUsage> {
+  return invoke<StorageUsage>('get_storage_usage')
+}
+
+/**
+ * Removes what `options` selects. Call with `dryRun` first and let the user
+ * confirm the listed items, then apply with those items as `reviewed`; only
+ * reviewed items that are still in the plan are removed.
+ */
+export async function cleanStorage(options: CleanupOptions, dryRun: boolean, reviewed?: CleanupItem[]): Promise<CleanupReport> {
+  return invoke<CleanupReport>('clean_storage', { options, dryRun, reviewed })
+}
+
+export interface ExportReport {
+  path: string
+  recordings: number
+  files: number
+  bytes: number
+}
+
+/** What an import does with recordings that already exist */
+export type ConflictPolicy = 'skip' | 'replace' | 'keepNewer'
+
+export interface ImportReport {
+  /** Recording ids new on this machine */
+  imported: string[]
+  replaced: string[]
+  skipped: string[]
+  aggregateFiles: number
+}
+
+/**
+ * Writes transcripts, insights and manifests (and optionally the audio) of
+ * managed storage to a zip archive at `destination`.
+ */
+export async function exportLibrary(destination: string, includeAudio: boolean): Promise<ExportReport> {
+  return invoke<ExportReport>('export_library', { destination, includeAudio })
+}
+
+/**
+ * Restores an archive from `exportLibrary`. Fails without changes if any
+ * file doesn't match the archive's checksums.
+ */
+export async function importLibrary(archive: string, conflict: ConflictPolicy): Promise<ImportReport> {
+  return invoke<ImportReport>('import_library', { archive, conflict })
+}
@@ -1,8 +1,8 @@
 import { invoke } from '@tauri-apps/api/core'
-import { Transcript } from './types'
+import { StoredTranscript, Transcript } from './types'
 
 /**
- * Transcribes an audio file using OpenAI Whisper API.
+ * Transcribes an audio file using the transcription provider selected in settings.
  * @param filePath Path to the audio file
  * @returns Transcript with text, word timestamps, duration, and language
  * @throws Error if transcription fails (missing API key, network error, etc.)
@@ -46,13 +46,13 @@ export async function saveTranscript(audioPath: string, text: string): Promise<v
 }
 
 /**
- * Reads transcript text from sidecar .txt file.
+ * Reads the structured transcript (words, segments, language, duration) from managed storage.
  * @param audioPath Path to the audio file
- * @returns Transcript text if file exists, null if file not found or error
+ * @returns Stored transcript if one exists, null if not found or error
  */
-export async function readTranscript(audioPath: string): Promise<string | null> {
+export async function readStoredTranscript(audioPath: string): Promise<StoredTranscript | null> {
   try {
-    return await invoke<string>('read_transcript', { path: audioPath })
+    return await invoke<StoredTranscript>('read_transcript', { path: audioPath })
   } catch (error) {
     // File not found or other error
     console.warn(`Failed to read transcript for ${audioPath}:`, error)
@@ -61,13 +61,25 @@ export async function readTranscript(audioPath: string): Promise<string | null>
 }
 
 /**
- * Generates AI summary of transcript text using OpenAI chat models.
+ * Reads transcript text from managed storage.
+ * @param audioPath Path to the audio file
+ * @returns Transcript text if file exists, null if file not found or error
+ */
+export async function readTranscript(audioPath: string): Promise<string | null> {
+  const stored = await readStoredTranscript(audioPath)
+  return stored?.text ?? null
+}
+
+/**
+ * Generates AI summary of transcript text with the chat provider configured
+ * for the recording's library.
  * @param text Transcript text to summarize
+ * @param path Recording the transcript belongs to; selects the chat provider
  * @returns Summary string (bullet points)
  * @throws Error if API key missing, network error, or empty text
  */
-export async function summarizeTranscript(text: string): Promise<string> {
-  return invoke<string>('summarize_transcript', { text })
+export async function summarizeTranscript(text: string, path?: string): Promise<string> {
+  return invoke<string>('summarize_transcript', { text, path })
 }
 
 export interface RecommendedAction {
@@ -75,12 +87,12 @@ export interface RecommendedAction {
   description: string
 }
 
-export async function recommendActions(text: string): Promise<RecommendedAction[]> {
-  return invoke<RecommendedAction[]>('recommend_actions', { text })
+export async function recommendActions(text: string, path?: string): Promise<RecommendedAction[]> {
+  return invoke<RecommendedAction[]>('recommend_actions', { text, path })
 }
 
-export async function extractKeyTopicsAI(text: string): Promise<string[]> {
-  return invoke<string[]>('extract_key_topics', { text })
+export async function extractKeyTopicsAI(text: string, path?: string): Promise<string[]> {
+  return invoke<string[]>('extract_key_topics', { text, path })
 }
 
 export interface TranscriptInsights {
@@ -89,6 +101,6 @@ export interface TranscriptInsights {
   topics?: string[]
 }
 
-export async function getTranscriptInsights(text: string): Promise<TranscriptInsights> {
-  return invoke<TranscriptInsights>('get_transcript_insights', { text })
+export async function getTranscriptInsights(text: string, path?: string): Promise<TranscriptInsights> {
+  return invoke<TranscriptInsights>('get_transcript_insights', { text, path })
 }
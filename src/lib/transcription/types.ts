@@ -4,13 +4,29 @@ export interface WordTimestamp {
   end: number
 }
 
+export interface SegmentTimestamp {
+  text: string
+  start: number
+  end: number
+}
+
 export interface Transcript {
   text: string
   words: WordTimestamp[]
+  segments?: SegmentTimestamp[]
   duration: number
   language: string
 }
 
+/**
+ * Contents of the managed transcript.json. Version 0 means the transcript was
+ * read from a legacy transcript.txt and only `text` is meaningful.
+ */
+export interface StoredTranscript extends Transcript {
+  version: number
+  edited: boolean
+}
+
 export interface TranscriptSegment {
   speaker: string
   text: string
@@ -5,4 +5,24 @@ export interface AudioItem {
   size: number;
   mtime: number;
   duration?: number;
+  /** Duration extrapolated from a partly unreadable file */
+  durationEstimated?: boolean;
+  /** Short codec name, e.g. mp3, aac, pcm_s16le */
+  codec?: string | null;
+  sampleRate?: number | null;
+  channels?: number | null;
+  /** Average bits per second */
+  bitrate?: number | null;
+  /** Embedded tags */
+  title?: string | null;
+  artist?: string | null;
+  date?: string | null;
+  comment?: string | null;
+  hasCoverArt?: boolean;
+  /** Start of the recording (Unix seconds); equals mtime when recordedAtSource is 'mtime' */
+  recordedAt?: number;
+  recordedAtSource?: RecordedAtSource;
 }
+
+/** Where recordedAt came from: BWF bext chunk, MP4 mvhd, ID3/MP4 date tag, file name or mtime */
+export type RecordedAtSource = 'bext' | 'mp4' | 'tag' | 'fileName' | 'mtime';
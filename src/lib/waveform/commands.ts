@@ -0,0 +1,33 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
+
+export interface WaveformData {
+  sampleRate: number
+  /** Seconds */
+  duration: number
+  /** Source frames per peak, fractional when peaks were merged to fit */
+  samplesPerPeak: number
+  /** Interleaved min, max per peak, in the i16 range */
+  peaks: number[]
+}
+
+export interface WaveformProgress {
+  path: string
+  /** Decoded fraction, 0 to 1 */
+  progress: number
+}
+
+/**
+ * Min/max peaks of a recording, at most `maxPeaks` of them (e.g. the width in
+ * pixels). The first call decodes the file; later calls read the cache.
+ */
+export async function getWaveformPeaks(filePath: string, maxPeaks: number): Promise<WaveformData> {
+  return invoke<WaveformData>('get_waveform_peaks', { path: filePath, maxPeaks })
+}
+
+/**
+ * Subscribes to decoding progress of `getWaveformPeaks` calls.
+ */
+export async function onWaveformProgress(handler: (progress: WaveformProgress) => void): Promise<UnlistenFn> {
+  return listen<WaveformProgress>('waveform-progress', (event) => handler(event.payload))
+}